@@ -0,0 +1,30 @@
+//! Builds the blocklist filter from the list of common passwords, so that
+//! only the filter ends up in the library.
+
+#[allow(dead_code)]
+#[path = "src/bloom.rs"]
+mod bloom;
+
+use bloom::BloomFilter;
+use std::path::PathBuf;
+
+fn main() {
+    let list: &str = "src/wordlists/blocklist_passwords.txt";
+    println!("cargo:rerun-if-changed={}", list);
+    println!("cargo:rerun-if-changed=src/bloom.rs");
+
+    let text: String = std::fs::read_to_string(list).expect("the common password list can be read");
+    let passwords: Vec<&str> = text.lines()
+        .skip_while(|line| line.starts_with('#'))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let mut filter = BloomFilter::with_capacity(passwords.len());
+    for password in &passwords {
+        filter.insert(password);
+    }
+
+    let out: PathBuf = PathBuf::from(std::env::var("OUT_DIR").expect("cargo sets OUT_DIR"));
+    std::fs::write(out.join("blocklist.bloom"), filter.as_bytes()).expect("the filter can be written");
+    std::fs::write(out.join("blocklist.len"), passwords.len().to_string()).expect("the count can be written");
+}
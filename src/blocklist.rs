@@ -0,0 +1,83 @@
+//! The most common leaked passwords, kept in a Bloom filter that is built
+//! with the crate, so that they take 16 bits each instead of the list.
+//!
+//! The list, wordlists/blocklist_passwords.txt, is sorted alphabetically
+//! and has no ranks, so it can only say whether a password is common. The
+//! strength checker also ranks passwords by how common they are, which
+//! takes the smaller zxcvbn frequency list in `COMMON_PASSWORDS`. The two
+//! overlap, but neither can stand in for the other.
+
+use crate::bloom::BloomFilter;
+
+/// How many passwords the blocklist holds, 99,838. The list is known as
+/// the top 100,000, but has that many different passwords.
+pub const BLOCKLIST_PASSWORDS: usize = include!(concat!(env!("OUT_DIR"), "/blocklist.len"));
+
+/// How often `is_common_password()` says yes to a password that is not in
+/// the blocklist: about once in 2180 passwords. It is
+/// (1 - e^(-k/b))^k for k = 11 bits set per password and b = 16 bits
+/// per password.
+pub const BLOCKLIST_FALSE_POSITIVE_RATE: f64 = 0.000_459;
+
+static FILTER: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/blocklist.bloom"));
+
+/// Whether `password` is one of the `BLOCKLIST_PASSWORDS` most common
+/// leaked passwords. Passwords in the list are always found, and other
+/// passwords are found by mistake at `BLOCKLIST_FALSE_POSITIVE_RATE`.
+/// Case matters.
+///
+/// Example:
+///
+/// ```
+/// # use password_generator::is_common_password;
+/// assert!(is_common_password("iloveyou"));
+/// assert!(!is_common_password("correct-horse-battery-staple"));
+/// ```
+pub fn is_common_password(password: &str) -> bool {
+    BloomFilter::from_bytes(FILTER).contains(password)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bloom::{BITS_PER_PASSWORD, HASHES};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+
+    /// The false positive rate the filter was sized for.
+    fn expected_false_positive_rate() -> f64 {
+        let hashes: f64 = HASHES as f64;
+        (1.0 - (-hashes / BITS_PER_PASSWORD as f64).exp()).powf(hashes)
+    }
+
+    #[test]
+    fn every_listed_password_is_found() {
+        let list: &str = include_str!("wordlists/blocklist_passwords.txt");
+        let passwords: Vec<&str> = list.lines().skip_while(|line| line.starts_with('#')).collect();
+
+        assert_eq!(passwords.len(), BLOCKLIST_PASSWORDS);
+        assert_eq!(FILTER.len(), (BLOCKLIST_PASSWORDS * BITS_PER_PASSWORD).div_ceil(8));
+        for password in passwords {
+            assert!(is_common_password(password), "{}", password);
+        }
+    }
+
+    #[test]
+    fn false_positives_are_as_rare_as_documented() {
+        assert!((expected_false_positive_rate() - BLOCKLIST_FALSE_POSITIVE_RATE).abs() < 1e-6);
+
+        // Random strings of 20 letters are never common, so every hit is a
+        // false positive. 400000 tries expect 184 of them, give or take 14.
+        let mut rng = ChaCha20Rng::seed_from_u64(22);
+        let tries: usize = 400_000;
+        let hits: usize = (0..tries)
+            .filter(|_| {
+                let password: String = (0..20).map(|_| rng.gen_range(b'a', b'z' + 1) as char).collect();
+                is_common_password(&password)
+            })
+            .count();
+        let expected: f64 = tries as f64 * BLOCKLIST_FALSE_POSITIVE_RATE;
+        assert!((hits as f64 - expected).abs() < 5.0 * expected.sqrt(), "{} false positives", hits);
+    }
+}
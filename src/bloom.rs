@@ -0,0 +1,66 @@
+//! A Bloom filter, shared with the build script that fills it.
+
+use std::borrow::Cow;
+
+/// Bits of the filter for every password in it.
+pub const BITS_PER_PASSWORD: usize = 16;
+
+/// Bits set for every password.
+pub const HASHES: u64 = 11;
+
+/// A set that answers "maybe" or "no": passwords that were inserted are
+/// always found, and others are found by mistake at a rate that depends
+/// on `BITS_PER_PASSWORD` and `HASHES`.
+pub struct BloomFilter<'a> {
+    bits: Cow<'a, [u8]>,
+}
+
+impl<'a> BloomFilter<'a> {
+    /// An empty filter with room for `passwords` passwords.
+    pub fn with_capacity(passwords: usize) -> Self {
+        let bytes: usize = (passwords.max(1) * BITS_PER_PASSWORD).div_ceil(8);
+        BloomFilter { bits: vec![0; bytes].into() }
+    }
+
+    /// A filter read back from `as_bytes()`.
+    pub fn from_bytes(bits: &'a [u8]) -> Self {
+        BloomFilter { bits: bits.into() }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    pub fn insert(&mut self, password: &str) {
+        for bit in self.positions(password) {
+            self.bits.to_mut()[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        !self.bits.is_empty() && self.positions(password).all(|bit| self.bits[(bit / 8) as usize] & 1 << (bit % 8) != 0)
+    }
+
+    /// The bits of `password`, from two hashes combined as in Kirsch and
+    /// Mitzenmacher's "Less Hashing, Same Performance".
+    fn positions(&self, password: &str) -> impl Iterator<Item = u64> {
+        let size: u64 = self.bits.len() as u64 * 8;
+        let hash: u64 = fnv1a(password.as_bytes());
+        let first: u64 = mix(hash);
+        let step: u64 = mix(hash ^ 0x9E37_79B9_7F4A_7C15) | 1;
+        (0..HASHES).map(move |i| first.wrapping_add(i.wrapping_mul(step)) % size)
+    }
+}
+
+/// 64-bit FNV-1a.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash: u64, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+/// The SplitMix64 finalizer, so that every bit of the hash depends on
+/// every bit of the input.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,152 @@
+//! Offline checks against a downloaded Have I Been Pwned password list.
+
+use crate::error::Error;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Length of a SHA-1 hash in hex.
+const HASH_LENGTH: usize = 40;
+
+/// A local copy of the Have I Been Pwned SHA-1 list, "ordered by hash".
+/// Every line is an uppercase SHA-1 hash of a password, a colon and the
+/// number of times it was seen in breaches, as in
+/// "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:10434004". The file is
+/// searched where it is, so lists of any size only cost a few reads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreachList {
+    path: PathBuf,
+}
+
+impl BreachList {
+    /// Uses the list at `path`, which must be readable.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let list = BreachList { path: path.as_ref().to_path_buf() };
+        list.file()?;
+        Ok(list)
+    }
+
+    /// Whether `password` is in the list.
+    pub fn contains(&self, password: &str) -> Result<bool, Error> {
+        Ok(self.count(password)?.is_some())
+    }
+
+    /// How many times `password` was seen in breaches, or None if it is
+    /// not in the list.
+    pub fn count(&self, password: &str) -> Result<Option<u64>, Error> {
+        let hash: String = format!("{:X}", Sha1::digest(password.as_bytes()));
+        let mut file: BufReader<File> = BufReader::new(self.file()?);
+        let size: u64 = file.get_ref().metadata().map_err(|error| self.unreadable(error))?.len();
+
+        // Binary search over byte offsets: look at the first line that
+        // starts at or after the middle, and keep the half it points to.
+        let (mut low, mut high): (u64, u64) = (0, size);
+        while low < high {
+            let middle: u64 = low + (high - low) / 2;
+            let (start, line) = self.line_at(&mut file, middle)?;
+
+            match line {
+                Some(line) => match compare(&line, &hash) {
+                    Ordering::Less => low = start + line.len() as u64,
+                    Ordering::Greater => high = middle,
+                    Ordering::Equal => {
+                        let count: &str = line.trim_end().get(HASH_LENGTH + 1..).unwrap_or("");
+                        return Ok(Some(count.parse().unwrap_or(1)));
+                    },
+                },
+                None => high = middle,
+            }
+        }
+        Ok(None)
+    }
+
+    /// The first line that starts at or after `position`, with where it
+    /// starts. None at the end of the file.
+    fn line_at(&self, file: &mut BufReader<File>, position: u64) -> Result<(u64, Option<String>), Error> {
+        let mut start: u64 = position;
+        let mut skipped: Vec<u8> = Vec::new();
+        if position > 0 {
+            file.seek(SeekFrom::Start(position - 1)).map_err(|error| self.unreadable(error))?;
+            start = position - 1 + file.read_until(b'\n', &mut skipped).map_err(|error| self.unreadable(error))? as u64;
+        } else {
+            file.seek(SeekFrom::Start(0)).map_err(|error| self.unreadable(error))?;
+        }
+
+        let mut line: Vec<u8> = Vec::new();
+        if file.read_until(b'\n', &mut line).map_err(|error| self.unreadable(error))? == 0 {
+            return Ok((start, None));
+        }
+        Ok((start, Some(String::from_utf8_lossy(&line).into_owned())))
+    }
+
+    fn file(&self) -> Result<File, Error> {
+        File::open(&self.path).map_err(|error| self.unreadable(error))
+    }
+
+    fn unreadable(&self, error: std::io::Error) -> Error {
+        Error::BreachListUnreadable(format!("{}: {}", self.path.display(), error))
+    }
+}
+
+/// Compares the hash a line starts with to `hash`, ignoring case.
+fn compare(line: &str, hash: &str) -> Ordering {
+    let listed: &str = line.get(..HASH_LENGTH).unwrap_or(line);
+    listed.to_ascii_uppercase().as_str().cmp(hash)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Writes a sorted list of the hashes of `passwords` and opens it.
+    fn breach_list(name: &str, passwords: &[String]) -> (BreachList, PathBuf) {
+        let mut lines: Vec<String> = passwords.iter()
+            .enumerate()
+            .map(|(i, password)| format!("{:X}:{}\r\n", Sha1::digest(password.as_bytes()), i + 1))
+            .collect();
+        lines.sort();
+
+        let path: PathBuf = std::env::temp_dir().join(format!("breaches-{}-{}.txt", name, std::process::id()));
+        fs::write(&path, lines.concat()).unwrap();
+        (BreachList::open(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn listed_passwords_are_found_with_their_counts() {
+        let passwords: Vec<String> = (0..1000).map(|i| format!("password{}", i)).collect();
+        let (list, path) = breach_list("counts", &passwords);
+
+        for (i, password) in passwords.iter().enumerate() {
+            assert_eq!(list.count(password).unwrap(), Some(i as u64 + 1), "{}", password);
+        }
+        assert!(!list.contains("password1000").unwrap());
+        assert!(!list.contains("").unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn first_last_and_only_lines_are_found() {
+        let (list, path) = breach_list("single", &[String::from("password")]);
+        assert!(list.contains("password").unwrap());
+        assert!(!list.contains("Password").unwrap());
+        fs::remove_file(path).unwrap();
+
+        let (list, path) = breach_list("empty", &[]);
+        assert!(!list.contains("password").unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn hashes_are_those_of_the_real_list() {
+        assert_eq!(format!("{:X}", Sha1::digest(b"password")), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+
+    #[test]
+    fn missing_lists_are_errors() {
+        assert!(matches!(BreachList::open("no/such/breaches.txt"), Err(Error::BreachListUnreadable(_))));
+    }
+}
@@ -0,0 +1,98 @@
+//! Named sets of characters, built in or your own.
+//!
+//! Every set a password draws from is a `CharacterSet`, and excluded
+//! characters are taken out of each set with `without()` before any are
+//! drawn.
+
+/// A named set of characters a password can be made of.
+///
+/// Example:
+///
+/// ```
+/// use password_generator::{CharacterSet, GeneratePassword};
+///
+/// let password = GeneratePassword::new()
+///     .length(16)
+///     .lowercase(true)
+///     .character_set(CharacterSet::new("safe symbols", "-_.+"))
+///     .generate();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharacterSet {
+    name: String,
+    characters: String,
+}
+
+impl CharacterSet {
+    /// A set called `name` made of `characters`. Repeated characters are
+    /// only kept once.
+    pub fn new(name: &str, characters: &str) -> Self {
+        let mut unique: String = String::new();
+        for c in characters.chars() {
+            if !unique.contains(c) {
+                unique.push(c);
+            }
+        }
+
+        CharacterSet {
+            name: name.to_string(),
+            characters: unique,
+        }
+    }
+
+    /// The name used in error messages and to refer to the set.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The characters of the set, in the order they were given.
+    pub fn characters(&self) -> &str {
+        &self.characters
+    }
+
+    /// How many characters the set has.
+    pub fn len(&self) -> usize {
+        self.characters.chars().count()
+    }
+
+    /// True if there is nothing to choose from.
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    /// True if `c` is in the set.
+    pub fn contains(&self, c: char) -> bool {
+        self.characters.contains(c)
+    }
+
+    /// The same set without any of the characters in `exclude`.
+    pub fn without(&self, exclude: &str) -> Self {
+        CharacterSet {
+            name: self.name.clone(),
+            characters: self.characters.chars().filter(|&c| !exclude.contains(c)).collect(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_characters_are_kept_once() {
+        let set = CharacterSet::new("vowels", "aeiouaa");
+
+        assert_eq!(set.characters(), "aeiou");
+        assert_eq!(set.len(), 5);
+    }
+
+    #[test]
+    fn characters_can_be_left_out() {
+        let set = CharacterSet::new("symbols", "!@#$%^&*()").without("&%^");
+
+        assert_eq!(set.characters(), "!@#$*()");
+        assert_eq!(set.name(), "symbols");
+        assert!(CharacterSet::new("symbols", "&%").without("&%").is_empty());
+    }
+}
@@ -0,0 +1,1143 @@
+//! Non-interactive command-line interface.
+
+use password_generator::{
+    check_strength, BreachList, Capitalization, CharacterSet, DeriveLessPass, DeriveSpectre, GeneratePassphrase,
+    GeneratePassword, GeneratePin, GeneratePronounceable, PolicyFile, Position, SpectreKey, SpectrePurpose, SpectreTemplate, Wordlist,
+};
+use std::io;
+
+pub static USAGE: &str = "\
+Usage: password_generator [COMMAND] [OPTIONS]
+
+Commands:
+    generate            Generate passwords (default)
+    check [PASSWORD]    Check that a password matches the options.
+                        Reads the password from stdin when it is not given.
+                        With --strength, estimate how easy it is to guess
+                        instead, from a score of 0 to 4. With
+                        --breaches FILE, look it up in a downloaded
+                        Have I Been Pwned list instead.
+    passphrase          Generate passphrases of random words instead, see
+                        Passphrase options below.
+    pronounceable       Generate passwords made of syllables that are
+                        easier to say and type, see Pronounceable options
+                        below.
+    pin                 Generate PINs that are not easy to guess, see PIN
+                        options below.
+    lesspass SITE LOGIN Derive the password of LOGIN on SITE from a master
+                        password read from stdin, the same one LessPass
+                        gives, see LessPass options below.
+    spectre NAME SITE   Derive the password on SITE from the full NAME and
+                        a master password read from stdin, the same one
+                        Spectre (Master Password) gives, see Spectre
+                        options below.
+
+Options:
+        --profile <NAME>
+                        Start from the settings of a profile, aws-iam,
+                        oracle or wifi, and change them with the options
+                        that follow. Give it before the other password
+                        options.
+        --policy <FILE> Read the profiles from FILE instead, TOML or JSON
+                        by its extension. Give it before --profile.
+    -l, --length <N>    Password length, at least 4 (default 4). A range
+                        such as 12-16 picks a random length in it.
+        --length-limit <N>
+                        Longest length that is allowed (default 4096)
+        --lower         Use lowercase letters
+        --upper         Use uppercase letters
+        --digits        Use numbers
+        --symbols       Use special characters, !@#$%^&*()
+        --all-symbols   Use all 32 ASCII punctuation characters as the
+                        special characters
+    -a, --all           Use every character set
+        --set <NAME=CHARS>
+                        Add a character set of your own, e.g. --set 'safe=-_.'
+    -x, --exclude <CHARS>
+                        Never use these characters
+    -u, --unambiguous   Leave out look-alike characters: 0Oo1lI|
+        --min <SET=N>   At least N characters from SET (default 1)
+        --max <SET=N>   At most N characters from SET
+        --max-repeat <N>
+                        At most N identical characters in a row
+        --max-sequence <N>
+                        At most N characters in a row going up or down by
+                        one, 2 rules out abc, 123 and cba
+        --max-set-run <N>
+                        At most N characters in a row from the same set
+        --first <SETS>  Start with a character from one of SETS, a comma
+                        separated list such as lower,upper
+        --last <SETS>   End with a character from one of SETS
+        --at <N=SETS>   Use one of SETS for the Nth character. N counts
+                        from 1, or from -1 for the last character.
+                        SET is lower, upper, digits, symbols or the name
+                        given to --set.
+        --reject-common Draw again while the password is one of the
+                        99,838 most common passwords
+        --breaches <FILE>
+                        Draw again while the password is in FILE, a
+                        downloaded Have I Been Pwned SHA-1 list ordered
+                        by hash
+    -c, --count <N>     Number of passwords to generate (default 1)
+    -h, --help          Print this message
+    -V, --version       Print the version
+
+Passphrase options:
+        --wordlist <FILE>
+                        Pick the words from FILE, one per line, with or
+                        without dice numbers (default EFF long wordlist)
+    -w, --words <N>     Number of words (default 6)
+    -s, --separator <SEP>
+                        What goes between the words (default -)
+        --case <CASE>   lower, upper or title (default lower)
+        --digit         Add a digit at the end
+        --symbol        Add a special character at the end
+    -c, --count <N>     Number of passphrases to generate (default 1)
+
+Pronounceable options:
+    -l, --length <N>    Password length, 4 to 128 (default 10)
+        --upper         Start one syllable with an uppercase letter
+        --digits        Put one digit between the syllables
+        --symbols       Put one special character between the syllables
+    -c, --count <N>     Number of passwords to generate (default 1)
+
+PIN options:
+    -l, --length <N>    Number of digits, 4 to 12 (default 4)
+        --allow-repeats Allow PINs such as 0000 or 1212
+        --allow-sequences
+                        Allow PINs such as 1234, 4321 or 2468
+        --allow-keypad  Allow PINs that follow a line on a keypad, such as
+                        2580
+        --allow-dates   Allow PINs that read as a date or a year
+        --common <N>    Leave out the N most common PINs (default 100)
+    -c, --count <N>     Number of PINs to generate (default 1)
+
+LessPass options:
+    -l, --length <N>    Password length, 5 to 35 (default 16)
+        --counter <N>   Raise to get a new password for the site (default 1)
+        --lower         Use lowercase letters
+        --upper         Use uppercase letters
+        --digits        Use numbers
+        --symbols       Use all 32 ASCII punctuation characters
+                        With none of these, all four are used.
+
+Spectre options:
+    -t, --template <T>  maximum, long, medium, short, basic, pin, name or
+                        phrase (default long, or name with --login and
+                        phrase with --answer)
+        --counter <N>   Raise to get a new password for the site (default 1)
+        --login         Derive a login name instead of a password
+        --answer        Derive an answer to a security question instead
+        --context <TEXT>
+                        Mix in TEXT, such as a keyword of the question
+
+With no character set options the password is a number. The number of
+possible passwords and their entropy, the bits of each word and of a
+whole passphrase, or the entropy of a pronounceable password or a PIN,
+are written to stderr.
+With no arguments at all, and stdin attached to a terminal, the
+interactive prompts are used instead.";
+
+/// What the command line asked for.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Generate { generator: GeneratePassword, count: usize },
+    Check { generator: GeneratePassword, password: Option<String> },
+    Strength { password: Option<String> },
+    Breached { list: BreachList, password: Option<String> },
+    Passphrase { generator: GeneratePassphrase, count: usize },
+    Pronounceable { generator: GeneratePronounceable, count: usize },
+    Pin { generator: GeneratePin, count: usize },
+    LessPass { deriver: DeriveLessPass },
+    Spectre { full_name: String, deriver: DeriveSpectre },
+    Help,
+    Version,
+}
+
+/// Parses the arguments, not including the program name.
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter().peekable();
+
+    let subcommand: &str = match args.peek() {
+        Some(arg) if !arg.starts_with('-') => {
+            let subcommand = args.next().unwrap();
+            match subcommand.as_str() {
+                "generate" | "check" => subcommand.as_str(),
+                "passphrase" => return parse_passphrase(args),
+                "pronounceable" => return parse_pronounceable(args),
+                "pin" => return parse_pin(args),
+                "lesspass" => return parse_lesspass(args),
+                "spectre" => return parse_spectre(args),
+                _ => return Err(format!("Unknown command '{}'.", subcommand)),
+            }
+        },
+        _ => "generate",
+    };
+
+    let mut generator = GeneratePassword::new();
+    let mut count: usize = 1;
+    let mut password: Option<String> = None;
+    let mut strength: bool = false;
+    let mut breaches: Option<BreachList> = None;
+    let mut policies: Option<PolicyFile> = None;
+    // Names of the custom sets, which take the place of the flag names of
+    // the built-in sets.
+    let mut custom_sets: Vec<String> = Vec::new();
+    // A profile replaces the whole generator, so nothing that changes it
+    // may come first.
+    let mut profile: bool = false;
+    let mut password_options: bool = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--policy" if profile => return Err(String::from("--policy must come before --profile.")),
+            "--profile" if password_options => {
+                return Err(String::from("--profile must come before the other password options."));
+            },
+            "--policy" => {
+                let file = PolicyFile::from_file(value_for(arg, args.next())?)
+                    .map_err(|error| format!("Invalid policy file: {}.", error))?;
+                policies = Some(file);
+            },
+            "--profile" => {
+                let value = value_for(arg, args.next())?;
+                let policies: PolicyFile = policies.clone().unwrap_or_else(PolicyFile::builtin);
+                let policy = policies.profile(value)
+                    .map_err(|error| format!("Invalid profile: {}.", error))?;
+                custom_sets = policy.sets.keys().cloned().collect();
+                generator = policy.generator();
+                profile = true;
+            },
+            "-l" | "--length" => {
+                let value = value_for(arg, args.next())?;
+                let invalid = || format!("Invalid length '{}', expected a number or a range like 12-16.", value);
+                generator = match value.split_once('-') {
+                    Some((min, max)) => generator.length_range(
+                        min.parse().map_err(|_| invalid())?,
+                        max.parse().map_err(|_| invalid())?,
+                    ),
+                    None => generator.length(value.parse().map_err(|_| invalid())?),
+                };
+            },
+            "--length-limit" => {
+                let value = value_for(arg, args.next())?;
+                let limit: usize = value.parse()
+                    .map_err(|_| format!("Invalid length limit '{}', expected a whole number.", value))?;
+                generator = generator.length_limit(limit);
+            },
+            "-c" | "--count" => {
+                let value = value_for(arg, args.next())?;
+                count = value.parse()
+                    .map_err(|_| format!("Invalid count '{}'.", value))?;
+            },
+            "--lower" => generator = generator.lowercase(true),
+            "--upper" => generator = generator.uppercase(true),
+            "--digits" => generator = generator.numbers(true),
+            "--symbols" => generator = generator.special_characters(true),
+            "--all-symbols" => generator = generator.extended_special_characters(true),
+            "-a" | "--all" => {
+                generator = generator
+                    .lowercase(true)
+                    .uppercase(true)
+                    .numbers(true)
+                    .special_characters(true);
+            },
+            "--set" => {
+                let value = value_for(arg, args.next())?;
+                let (name, characters) = value.split_once('=')
+                    .ok_or_else(|| format!("Invalid set '{}', expected NAME=CHARACTERS.", value))?;
+                generator = generator.character_set(CharacterSet::new(name, characters));
+                custom_sets.push(name.to_string());
+            },
+            "--min" | "--max" => {
+                let value = value_for(arg, args.next())?;
+                let (set, count) = value.split_once('=')
+                    .and_then(|(set, count)| Some((set_name(set, &custom_sets), count.parse().ok()?)))
+                    .ok_or_else(|| format!("Invalid count '{}', expected SET=N.", value))?;
+                generator = if arg == "--min" {
+                    generator.minimum(set, count)
+                } else {
+                    generator.maximum(set, count)
+                };
+            },
+            "--first" | "--last" | "--at" => {
+                let value = value_for(arg, args.next())?;
+                let (position, sets) = match arg.as_str() {
+                    "--first" => (Position::FromStart(0), value.as_str()),
+                    "--last" => (Position::FromEnd(0), value.as_str()),
+                    _ => value.split_once('=')
+                        .and_then(|(index, sets)| Some((position(index)?, sets)))
+                        .ok_or_else(|| format!("Invalid position '{}', expected N=SETS with N not 0.", value))?,
+                };
+                let sets: Vec<&str> = sets.split(',').map(|set| set_name(set, &custom_sets)).collect();
+                generator = generator.at(position, &sets);
+            },
+            "--max-repeat" | "--max-sequence" | "--max-set-run" => {
+                let value = value_for(arg, args.next())?;
+                let count: usize = value.parse()
+                    .map_err(|_| format!("Invalid run length '{}', expected a whole number.", value))?;
+                generator = match arg.as_str() {
+                    "--max-repeat" => generator.max_repeats(count),
+                    "--max-sequence" => generator.max_sequence(count),
+                    _ => generator.max_same_set_run(count),
+                };
+            },
+            "-u" | "--unambiguous" => generator = generator.unambiguous(true),
+            "-x" | "--exclude" => {
+                let value = value_for(arg, args.next())?;
+                generator = generator.exclude(value);
+            },
+            "--reject-common" => generator = generator.reject_common(true),
+            "--breaches" => {
+                let list = BreachList::open(value_for(arg, args.next())?)
+                    .map_err(|error| format!("Invalid breach list: {}.", error))?;
+                breaches = Some(list);
+            },
+            "--strength" if subcommand == "check" => strength = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ if subcommand == "check" && password.is_none() => password = Some(arg.clone()),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+        let keeps_profile: bool = matches!(
+            arg.as_str(),
+            "--policy" | "--profile" | "-c" | "--count" | "--breaches" | "--strength"
+        );
+        if arg.starts_with('-') && !keeps_profile {
+            password_options = true;
+        }
+    }
+
+    match subcommand {
+        "check" if breaches.is_some() => {
+            if strength || generator != GeneratePassword::new() {
+                return Err(String::from("--breaches does not take --strength or password options."));
+            }
+            Ok(Command::Breached { list: breaches.unwrap(), password })
+        },
+        "check" if strength => {
+            if generator != GeneratePassword::new() {
+                return Err(String::from("--strength does not take password options."));
+            }
+            Ok(Command::Strength { password })
+        },
+        "check" => Ok(Command::Check { generator, password }),
+        _ => {
+            if let Some(list) = breaches {
+                generator = generator.breach_list(list);
+            }
+            Ok(Command::Generate { generator, count })
+        },
+    }
+}
+
+/// Parses the options of the passphrase command.
+fn parse_passphrase<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut generator = GeneratePassphrase::new();
+    let mut count: usize = 1;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--wordlist" => {
+                let wordlist = Wordlist::from_file(value_for(arg, args.next())?)
+                    .map_err(|error| format!("Invalid wordlist: {}.", error))?;
+                generator = generator.wordlist(wordlist);
+            },
+            "-w" | "--words" => {
+                let value = value_for(arg, args.next())?;
+                let words: usize = value.parse()
+                    .map_err(|_| format!("Invalid number of words '{}'.", value))?;
+                generator = generator.words(words);
+            },
+            "-s" | "--separator" => generator = generator.separator(value_for(arg, args.next())?),
+            "--case" => {
+                let value = value_for(arg, args.next())?;
+                generator = generator.capitalization(match value.as_str() {
+                    "lower" => Capitalization::Lowercase,
+                    "upper" => Capitalization::Uppercase,
+                    "title" => Capitalization::Title,
+                    _ => return Err(format!("Invalid case '{}', expected lower, upper or title.", value)),
+                });
+            },
+            "--digit" => generator = generator.digit(true),
+            "--symbol" => generator = generator.special_character(true),
+            "-c" | "--count" => {
+                let value = value_for(arg, args.next())?;
+                count = value.parse()
+                    .map_err(|_| format!("Invalid count '{}'.", value))?;
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+    }
+    Ok(Command::Passphrase { generator, count })
+}
+
+/// Parses the options of the pronounceable command.
+fn parse_pronounceable<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut generator = GeneratePronounceable::new();
+    let mut count: usize = 1;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-l" | "--length" => {
+                let value = value_for(arg, args.next())?;
+                let length: usize = value.parse()
+                    .map_err(|_| format!("Invalid length '{}', expected a number.", value))?;
+                generator = generator.length(length);
+            },
+            "--upper" => generator = generator.uppercase(true),
+            "--digits" => generator = generator.numbers(true),
+            "--symbols" => generator = generator.special_characters(true),
+            "-c" | "--count" => {
+                let value = value_for(arg, args.next())?;
+                count = value.parse()
+                    .map_err(|_| format!("Invalid count '{}'.", value))?;
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+    }
+    Ok(Command::Pronounceable { generator, count })
+}
+
+/// Parses the options of the pin command.
+fn parse_pin<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut generator = GeneratePin::new();
+    let mut count: usize = 1;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-l" | "--length" => {
+                let value = value_for(arg, args.next())?;
+                let length: usize = value.parse()
+                    .map_err(|_| format!("Invalid length '{}', expected a number.", value))?;
+                generator = generator.length(length);
+            },
+            "--allow-repeats" => generator = generator.reject_repeats(false),
+            "--allow-sequences" => generator = generator.reject_sequences(false),
+            "--allow-keypad" => generator = generator.reject_keypad_patterns(false),
+            "--allow-dates" => generator = generator.reject_dates(false),
+            "--common" => {
+                let value = value_for(arg, args.next())?;
+                let common: usize = value.parse()
+                    .map_err(|_| format!("Invalid number of common PINs '{}'.", value))?;
+                generator = generator.reject_common(common);
+            },
+            "-c" | "--count" => {
+                let value = value_for(arg, args.next())?;
+                count = value.parse()
+                    .map_err(|_| format!("Invalid count '{}'.", value))?;
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+    }
+    Ok(Command::Pin { generator, count })
+}
+
+/// Parses the site, login and options of the lesspass command.
+fn parse_lesspass<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut names: Vec<&str> = Vec::new();
+    let mut length: usize = 16;
+    let mut counter: u32 = 1;
+    let mut classes: Vec<&str> = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-l" | "--length" => {
+                let value = value_for(arg, args.next())?;
+                length = value.parse()
+                    .map_err(|_| format!("Invalid length '{}', expected a number.", value))?;
+            },
+            "--counter" => {
+                let value = value_for(arg, args.next())?;
+                counter = value.parse()
+                    .map_err(|_| format!("Invalid counter '{}', expected a whole number.", value))?;
+            },
+            "--lower" | "--upper" | "--digits" | "--symbols" => classes.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ if names.len() < 2 => names.push(arg),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+    }
+
+    let (site, login) = match names[..] {
+        [site, login] => (site, login),
+        _ => return Err(String::from("lesspass needs a site and a login.")),
+    };
+    let mut deriver = DeriveLessPass::new(site, login).length(length).counter(counter);
+    if !classes.is_empty() {
+        deriver = deriver
+            .lowercase(classes.contains(&"--lower"))
+            .uppercase(classes.contains(&"--upper"))
+            .numbers(classes.contains(&"--digits"))
+            .special_characters(classes.contains(&"--symbols"));
+    }
+    Ok(Command::LessPass { deriver })
+}
+
+/// Parses the full name, site and options of the spectre command.
+fn parse_spectre<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut names: Vec<&str> = Vec::new();
+    let mut template: Option<SpectreTemplate> = None;
+    let mut counter: u32 = 1;
+    let mut purpose: SpectrePurpose = SpectrePurpose::Authentication;
+    let mut context: Option<&str> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-t" | "--template" => {
+                let value = value_for(arg, args.next())?;
+                template = Some(match value.as_str() {
+                    "maximum" => SpectreTemplate::Maximum,
+                    "long" => SpectreTemplate::Long,
+                    "medium" => SpectreTemplate::Medium,
+                    "short" => SpectreTemplate::Short,
+                    "basic" => SpectreTemplate::Basic,
+                    "pin" => SpectreTemplate::Pin,
+                    "name" => SpectreTemplate::Name,
+                    "phrase" => SpectreTemplate::Phrase,
+                    _ => return Err(format!("Invalid template '{}'.", value)),
+                });
+            },
+            "--counter" => {
+                let value = value_for(arg, args.next())?;
+                counter = value.parse()
+                    .map_err(|_| format!("Invalid counter '{}', expected a whole number.", value))?;
+            },
+            "--login" => purpose = SpectrePurpose::Identification,
+            "--answer" => purpose = SpectrePurpose::Recovery,
+            "--context" => context = Some(value_for(arg, args.next())?),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ if names.len() < 2 => names.push(arg),
+            _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+    }
+
+    let (full_name, site) = match names[..] {
+        [full_name, site] => (full_name, site),
+        _ => return Err(String::from("spectre needs a full name and a site.")),
+    };
+    let template: SpectreTemplate = template.unwrap_or_else(|| purpose.default_template());
+    let mut deriver = DeriveSpectre::new(site).template(template).counter(counter).purpose(purpose);
+    if let Some(context) = context {
+        deriver = deriver.context(context);
+    }
+    Ok(Command::Spectre { full_name: full_name.to_string(), deriver })
+}
+
+/// The library's name for a set given on the command line. A custom set
+/// called "symbols" is meant over the special characters.
+fn set_name<'a>(set: &'a str, custom_sets: &[String]) -> &'a str {
+    if custom_sets.iter().any(|name| name == set) {
+        return set;
+    }
+    match set {
+        "lower" => "lowercase",
+        "upper" => "uppercase",
+        "digits" => "numbers",
+        "symbols" => "special characters",
+        _ => set,
+    }
+}
+
+/// A position given on the command line, counted from 1 or from -1 at
+/// the end.
+fn position(index: &str) -> Option<Position> {
+    let index: isize = index.parse().ok()?;
+    match index {
+        0 => None,
+        _ if index > 0 => Some(Position::FromStart(index as usize - 1)),
+        _ => Some(Position::FromEnd((-index) as usize - 1)),
+    }
+}
+
+fn value_for<'a>(option: &str, value: Option<&'a String>) -> Result<&'a String, String> {
+    value.ok_or_else(|| format!("Option '{}' needs a value.", option))
+}
+
+/// Runs a parsed command and returns the process exit code.
+pub fn run(command: Command) -> i32 {
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            0
+        },
+        Command::Version => {
+            println!("password_generator {}", env!("CARGO_PKG_VERSION"));
+            0
+        },
+        Command::Generate { generator, count } => {
+            for _ in 0..count {
+                match generator.try_generate() {
+                    Ok(password) => println!("{}", password),
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        return 1;
+                    },
+                }
+            }
+            // On stderr, so that only the passwords go to a pipe.
+            if let Ok(report) = generator.entropy_report() {
+                eprintln!("{}", report);
+            }
+            0
+        },
+        Command::Passphrase { generator, count } => {
+            for _ in 0..count {
+                match generator.try_generate() {
+                    Ok(passphrase) => println!("{}", passphrase),
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        return 1;
+                    },
+                }
+            }
+            if let (Ok(bits_per_word), Ok(bits)) = (generator.bits_per_word(), generator.entropy_bits()) {
+                eprintln!("{:.1} bits per word, {:.1} bits of entropy", bits_per_word, bits);
+            }
+            0
+        },
+        Command::Pronounceable { generator, count } => {
+            for _ in 0..count {
+                match generator.try_generate() {
+                    Ok(password) => println!("{}", password),
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        return 1;
+                    },
+                }
+            }
+            if let Ok(bits) = generator.entropy_bits() {
+                eprintln!("{:.1} bits of entropy", bits);
+            }
+            0
+        },
+        Command::Pin { generator, count } => {
+            for _ in 0..count {
+                match generator.try_generate() {
+                    Ok(pin) => println!("{}", pin),
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        return 1;
+                    },
+                }
+            }
+            if let Ok(bits) = generator.entropy_bits() {
+                eprintln!("{:.1} bits of entropy", bits);
+            }
+            0
+        },
+        Command::LessPass { deriver } => {
+            // Only from stdin, so that it stays out of the shell history.
+            let master_password: String = match read_password() {
+                Some(password) => password,
+                None => return 2,
+            };
+
+            match deriver.derive(&master_password) {
+                Ok(password) => {
+                    println!("{}", password);
+                    0
+                },
+                Err(error) => {
+                    eprintln!("Error: {}", error);
+                    1
+                },
+            }
+        },
+        Command::Spectre { full_name, deriver } => {
+            let master_password: String = match read_password() {
+                Some(password) => password,
+                None => return 2,
+            };
+
+            println!("{}", deriver.derive(&SpectreKey::new(&full_name, &master_password)));
+            0
+        },
+        Command::Check { generator, password } => {
+            let password: String = match password.or_else(read_password) {
+                Some(password) => password,
+                None => return 2,
+            };
+
+            if generator.matches(&password) {
+                println!("OK");
+                0
+            } else {
+                println!("Password does not match the options.");
+                1
+            }
+        },
+        Command::Strength { password } => {
+            let password: String = match password.or_else(read_password) {
+                Some(password) => password,
+                None => return 2,
+            };
+
+            let strength = check_strength(&password);
+            println!("Score: {}/4, about 10^{:.1} guesses", strength.score(), strength.guesses_log10());
+            for line in strength.feedback() {
+                println!("{}", line);
+            }
+            if strength.score() >= 3 { 0 } else { 1 }
+        },
+        Command::Breached { list, password } => {
+            let password: String = match password.or_else(read_password) {
+                Some(password) => password,
+                None => return 2,
+            };
+
+            match list.count(&password) {
+                Ok(Some(count)) => {
+                    println!("Found in breaches {} times.", count);
+                    1
+                },
+                Ok(None) => {
+                    println!("Not found in breaches.");
+                    0
+                },
+                Err(error) => {
+                    eprintln!("Error: {}", error);
+                    2
+                },
+            }
+        },
+    }
+}
+
+/// Reads a password from a line of stdin.
+fn read_password() -> Option<String> {
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() {
+        eprintln!("Failed to read line!");
+        return None;
+    }
+    Some(input.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn no_arguments_generates_one_default_password() {
+        assert_eq!(
+            parse(&args("")),
+            Ok(Command::Generate { generator: GeneratePassword::new(), count: 1 })
+        );
+    }
+
+    #[test]
+    fn flags_map_onto_generate_password() {
+        assert_eq!(
+            parse(&args("generate --length 20 --lower --digits -c 3")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new().length(20).lowercase(true).numbers(true),
+                count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn all_uses_every_character_set() {
+        assert_eq!(
+            parse(&args("-a -l 12")),
+            Ok(Command::Generate { generator: GeneratePassword::all_characters(12), count: 1 })
+        );
+    }
+
+    #[test]
+    fn length_can_be_a_range() {
+        assert_eq!(
+            parse(&args("--length 12-16")),
+            Ok(Command::Generate { generator: GeneratePassword::new().length_range(12, 16), count: 1 })
+        );
+        assert!(parse(&args("--length 12-")).is_err());
+    }
+
+    #[test]
+    fn length_limit_can_be_raised() {
+        assert_eq!(
+            parse(&args("-l 8000 --length-limit 10000")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new().length(8000).length_limit(10000),
+                count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn custom_sets_and_exclusions_map_onto_generate_password() {
+        assert_eq!(
+            parse(&args("--lower --set safe=-_. -x lo -u")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new()
+                    .lowercase(true)
+                    .character_set(CharacterSet::new("safe", "-_."))
+                    .exclude("lo")
+                    .unambiguous(true),
+                count: 1,
+            })
+        );
+        assert!(parse(&args("--set safe")).is_err());
+    }
+
+    #[test]
+    fn all_symbols_uses_the_extended_special_characters() {
+        assert_eq!(
+            parse(&args("-l 10 --all-symbols")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new().length(10).extended_special_characters(true),
+                count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn minimums_and_maximums_use_the_flag_names_of_the_sets() {
+        assert_eq!(
+            parse(&args("-a -l 12 --min digits=2 --min symbols=2 --max symbols=4 --max safe=1")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::all_characters(12)
+                    .minimum("numbers", 2)
+                    .minimum("special characters", 2)
+                    .maximum("special characters", 4)
+                    .maximum("safe", 1),
+                count: 1,
+            })
+        );
+        assert!(parse(&args("--min digits")).is_err());
+        assert!(parse(&args("--max digits=many")).is_err());
+    }
+
+    #[test]
+    fn positions_take_lists_of_sets() {
+        assert_eq!(
+            parse(&args("-a -l 12 --first lower,upper --last digits --at 2=symbols --at -3=lower")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::all_characters(12)
+                    .starts_with(&["lowercase", "uppercase"])
+                    .ends_with(&["numbers"])
+                    .at(Position::FromStart(1), &["special characters"])
+                    .at(Position::FromEnd(2), &["lowercase"]),
+                count: 1,
+            })
+        );
+        assert!(parse(&args("--at 0=lower")).is_err());
+        assert!(parse(&args("--at lower")).is_err());
+    }
+
+    #[test]
+    fn runs_can_be_limited() {
+        assert_eq!(
+            parse(&args("-a -l 12 --max-repeat 2 --max-sequence 2 --max-set-run 3")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::all_characters(12)
+                    .max_repeats(2)
+                    .max_sequence(2)
+                    .max_same_set_run(3),
+                count: 1,
+            })
+        );
+        assert!(parse(&args("--max-repeat two")).is_err());
+    }
+
+    #[test]
+    fn passphrase_has_its_own_options() {
+        assert_eq!(
+            parse(&args("passphrase -w 5 -s . --case title --digit --symbol -c 2")),
+            Ok(Command::Passphrase {
+                generator: GeneratePassphrase::new()
+                    .words(5)
+                    .separator(".")
+                    .capitalization(Capitalization::Title)
+                    .digit(true)
+                    .special_character(true),
+                count: 2,
+            })
+        );
+        assert_eq!(
+            parse(&args("passphrase")),
+            Ok(Command::Passphrase { generator: GeneratePassphrase::new(), count: 1 })
+        );
+        assert!(parse(&args("passphrase --case sentence")).is_err());
+        assert!(parse(&args("passphrase --wordlist no/such/wordlist.txt")).is_err());
+        assert!(parse(&args("passphrase --lower")).is_err());
+    }
+
+    #[test]
+    fn passphrase_words_can_come_from_a_file() {
+        let path = std::env::temp_dir().join(format!("wordlist-{}.txt", std::process::id()));
+        let text: String = (0..1296).map(|i| format!("{}\tpalabra{}\n", 11111 + i, i)).collect();
+        std::fs::write(&path, &text).unwrap();
+        let parsed = parse(&args(&format!("passphrase --wordlist {}", path.display())));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            parsed,
+            Ok(Command::Passphrase {
+                generator: GeneratePassphrase::new().wordlist(Wordlist::parse(&text).unwrap()),
+                count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn pronounceable_has_its_own_options() {
+        assert_eq!(
+            parse(&args("pronounceable -l 12 --upper --digits --symbols -c 3")),
+            Ok(Command::Pronounceable {
+                generator: GeneratePronounceable::new()
+                    .length(12)
+                    .uppercase(true)
+                    .numbers(true)
+                    .special_characters(true),
+                count: 3,
+            })
+        );
+        assert!(parse(&args("pronounceable --lower")).is_err());
+        assert!(parse(&args("pronounceable -l twelve")).is_err());
+    }
+
+    #[test]
+    fn pin_rules_can_be_turned_off() {
+        assert_eq!(
+            parse(&args("pin -l 6 --allow-repeats --allow-sequences --allow-keypad --allow-dates --common 0 -c 2")),
+            Ok(Command::Pin {
+                generator: GeneratePin::new()
+                    .length(6)
+                    .reject_repeats(false)
+                    .reject_sequences(false)
+                    .reject_keypad_patterns(false)
+                    .reject_dates(false)
+                    .reject_common(0),
+                count: 2,
+            })
+        );
+        assert_eq!(parse(&args("pin")), Ok(Command::Pin { generator: GeneratePin::new(), count: 1 }));
+        assert!(parse(&args("pin --common lots")).is_err());
+    }
+
+    #[test]
+    fn lesspass_takes_a_site_and_a_login() {
+        assert_eq!(
+            parse(&args("lesspass example.org contact@example.org")),
+            Ok(Command::LessPass { deriver: DeriveLessPass::new("example.org", "contact@example.org") })
+        );
+        assert_eq!(
+            parse(&args("lesspass example.org me -l 14 --counter 2 --lower --digits")),
+            Ok(Command::LessPass {
+                deriver: DeriveLessPass::new("example.org", "me")
+                    .length(14)
+                    .counter(2)
+                    .uppercase(false)
+                    .special_characters(false),
+            })
+        );
+        assert!(parse(&args("lesspass example.org")).is_err());
+        assert!(parse(&args("lesspass example.org me extra")).is_err());
+        assert!(parse(&args("lesspass example.org me --counter -1")).is_err());
+    }
+
+    #[test]
+    fn spectre_takes_a_full_name_and_a_site() {
+        assert_eq!(
+            parse(&[String::from("spectre"), String::from("Robert Lee Mitchell"), String::from("example.org")]),
+            Ok(Command::Spectre {
+                full_name: String::from("Robert Lee Mitchell"),
+                deriver: DeriveSpectre::new("example.org"),
+            })
+        );
+        assert_eq!(
+            parse(&args("spectre Robert example.org -t phrase --counter 3 --answer --context pet")),
+            Ok(Command::Spectre {
+                full_name: String::from("Robert"),
+                deriver: DeriveSpectre::new("example.org")
+                    .template(SpectreTemplate::Phrase)
+                    .counter(3)
+                    .purpose(SpectrePurpose::Recovery)
+                    .context("pet"),
+            })
+        );
+        assert!(parse(&args("spectre Robert")).is_err());
+        assert!(parse(&args("spectre Robert example.org -t huge")).is_err());
+    }
+
+    #[test]
+    fn spectre_logins_and_answers_use_their_own_templates() {
+        let key = SpectreKey::new("Robert Lee Mitchell", "banana colored duckling");
+        let derive = |line: &str| match parse(&args(line)) {
+            Ok(Command::Spectre { deriver, .. }) => deriver.derive(&key),
+            parsed => panic!("{:?}", parsed),
+        };
+
+        assert_eq!(derive("spectre Robert masterpasswordapp.com --login"), "wohzaqage");
+        assert_eq!(derive("spectre Robert masterpasswordapp.com --answer"), "xin diyjiqoja hubu");
+        assert_eq!(derive("spectre Robert masterpasswordapp.com --answer --context question"), "xogx tem cegyiva jab");
+        assert_eq!(
+            parse(&args("spectre Robert example.org --login -t long")),
+            Ok(Command::Spectre {
+                full_name: String::from("Robert"),
+                deriver: DeriveSpectre::new("example.org")
+                    .template(SpectreTemplate::Long)
+                    .purpose(SpectrePurpose::Identification),
+            })
+        );
+    }
+
+    #[test]
+    fn check_takes_a_password() {
+        assert_eq!(
+            parse(&args("check --upper -l 5 ABCDE")),
+            Ok(Command::Check {
+                generator: GeneratePassword::new().length(5).uppercase(true),
+                password: Some(String::from("ABCDE")),
+            })
+        );
+    }
+
+    #[test]
+    fn check_can_estimate_strength() {
+        assert_eq!(
+            parse(&args("check --strength hunter2")),
+            Ok(Command::Strength { password: Some(String::from("hunter2")) })
+        );
+        assert_eq!(parse(&args("check --strength")), Ok(Command::Strength { password: None }));
+        assert!(parse(&args("check --strength --upper hunter2")).is_err());
+        assert!(parse(&args("generate --strength")).is_err());
+    }
+
+    #[test]
+    fn common_and_breached_passwords_can_be_left_out() {
+        let path = std::env::temp_dir().join(format!("cli-breaches-{}.txt", std::process::id()));
+        std::fs::write(&path, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:10434004\r\n").unwrap();
+        let list = BreachList::open(&path).unwrap();
+
+        assert_eq!(
+            parse(&args(&format!("-l 8 --lower --reject-common --breaches {}", path.display()))),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new()
+                    .length(8)
+                    .lowercase(true)
+                    .reject_common(true)
+                    .breach_list(list.clone()),
+                count: 1,
+            })
+        );
+        assert_eq!(
+            parse(&args(&format!("check --breaches {} password", path.display()))),
+            Ok(Command::Breached { list, password: Some(String::from("password")) })
+        );
+        assert!(parse(&args(&format!("check --breaches {} --strength", path.display()))).is_err());
+        assert!(parse(&args(&format!("check --breaches {} --upper", path.display()))).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(parse(&args("--breaches no/such/breaches.txt")).is_err());
+    }
+
+    #[test]
+    fn profiles_can_be_changed_by_later_options() {
+        assert_eq!(
+            parse(&args("--profile wifi")),
+            Ok(Command::Generate { generator: GeneratePassword::from_profile("wifi").unwrap(), count: 1 })
+        );
+        assert_eq!(
+            parse(&args("--profile wifi -l 30")),
+            Ok(Command::Generate { generator: GeneratePassword::from_profile("wifi").unwrap().length(30), count: 1 })
+        );
+        assert!(parse(&args("--profile mainframe")).is_err());
+    }
+
+    #[test]
+    fn profiles_must_come_before_what_they_would_replace() {
+        assert_eq!(
+            parse(&args("-c 2 --profile wifi")),
+            Ok(Command::Generate { generator: GeneratePassword::from_profile("wifi").unwrap(), count: 2 })
+        );
+        assert_eq!(
+            parse(&args("-l 30 --profile wifi")),
+            Err(String::from("--profile must come before the other password options."))
+        );
+        assert_eq!(
+            parse(&args("--upper --profile wifi --lower")),
+            Err(String::from("--profile must come before the other password options."))
+        );
+        assert_eq!(
+            parse(&args("--profile wifi --policy no/such/policy.toml")),
+            Err(String::from("--policy must come before --profile."))
+        );
+    }
+
+    #[test]
+    fn custom_sets_are_found_before_the_flag_names() {
+        assert_eq!(
+            parse(&args("--profile oracle --max symbols=2 --min symbols=1 --first lower")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::from_profile("oracle").unwrap()
+                    .maximum("symbols", 2)
+                    .minimum("symbols", 1)
+                    .starts_with(&["lowercase"]),
+                count: 1,
+            })
+        );
+        assert_eq!(
+            parse(&args("--lower --set symbols=#_ --at -1=symbols")),
+            Ok(Command::Generate {
+                generator: GeneratePassword::new()
+                    .lowercase(true)
+                    .character_set(CharacterSet::new("symbols", "#_"))
+                    .at(Position::FromEnd(0), &["symbols"]),
+                count: 1,
+            })
+        );
+        let password: String = match parse(&args("--profile oracle --max symbols=2")) {
+            Ok(Command::Generate { generator, .. }) => generator.try_generate().unwrap(),
+            parsed => panic!("{:?}", parsed),
+        };
+        assert!(password.chars().filter(|c| "#_$".contains(*c)).count() <= 2, "{}", password);
+    }
+
+    #[test]
+    fn profiles_can_come_from_a_policy_file() {
+        let path = std::env::temp_dir().join(format!("cli-policy-{}.toml", std::process::id()));
+        std::fs::write(&path, "[profiles.vpn]\nlength = 32\nlowercase = true\n").unwrap();
+        let parsed = parse(&args(&format!("--policy {} --profile vpn", path.display())));
+        let missing = parse(&args(&format!("--policy {} --profile wifi", path.display())));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            parsed,
+            Ok(Command::Generate { generator: GeneratePassword::new().length(32).lowercase(true), count: 1 })
+        );
+        assert!(missing.is_err());
+        assert!(parse(&args("--policy no/such/policy.toml")).is_err());
+    }
+
+    #[test]
+    fn invalid_arguments_are_errors() {
+        assert!(parse(&args("--length -3")).is_err());
+        assert!(parse(&args("--length ten")).is_err());
+        assert!(parse(&args("--length")).is_err());
+        assert!(parse(&args("--colour")).is_err());
+        assert!(parse(&args("shuffle")).is_err());
+        assert!(parse(&args("generate extra")).is_err());
+    }
+}
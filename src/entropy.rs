@@ -0,0 +1,79 @@
+//! How many passwords a configuration can generate.
+
+use std::fmt;
+
+/// The number of passwords `generate()` can return and how many bits of
+/// entropy that is, next to what the same lengths and characters would
+/// give without any rules.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntropyReport {
+    ln_passwords: f64,
+    bits: f64,
+    unconstrained_bits: f64,
+}
+
+impl EntropyReport {
+    pub(crate) fn new(ln_passwords: f64, bits: f64, unconstrained_bits: f64) -> Self {
+        EntropyReport { ln_passwords, bits, unconstrained_bits }
+    }
+
+    /// How many different passwords can be generated, counted exactly and
+    /// then rounded to a `f64`. Counts too large for a `f64` are infinity,
+    /// see `log10_passwords()`.
+    pub fn passwords(&self) -> f64 {
+        self.ln_passwords.exp()
+    }
+
+    /// The base 10 log of the number of passwords, which never overflows.
+    pub fn log10_passwords(&self) -> f64 {
+        self.ln_passwords / std::f64::consts::LN_10
+    }
+
+    /// How many bits of entropy a generated password has.
+    pub fn bits(&self) -> f64 {
+        self.bits
+    }
+
+    /// How many bits a password would have if every character was picked
+    /// from all the sets, with no guarantees or other rules.
+    pub fn unconstrained_bits(&self) -> f64 {
+        self.unconstrained_bits
+    }
+
+    /// How many bits the guarantees and other rules take away.
+    pub fn bits_lost(&self) -> f64 {
+        (self.unconstrained_bits - self.bits).max(0.0)
+    }
+}
+
+impl fmt::Display for EntropyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let log10: f64 = self.log10_passwords();
+        if log10 < 15.0 {
+            write!(f, "{:.0} possible passwords", self.passwords())?;
+        } else {
+            let exponent: f64 = log10.floor();
+            write!(f, "{:.2}e{} possible passwords", 10f64.powf(log10 - exponent), exponent)?;
+        }
+        write!(f, ", {:.1} bits of entropy", self.bits)?;
+        if self.bits_lost() >= 0.05 {
+            write!(f, " ({:.1} bits less than without the rules)", self.bits_lost())?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_reads_well() {
+        let report = EntropyReport::new(10_000f64.ln(), 13.2877, 13.2877);
+        assert_eq!(report.to_string(), "10000 possible passwords, 13.3 bits of entropy");
+
+        let report = EntropyReport::new(1e30f64.ln() + 2f64.ln(), 100.66, 104.0);
+        assert_eq!(report.to_string(), "2.00e30 possible passwords, 100.7 bits of entropy (3.3 bits less than without the rules)");
+    }
+}
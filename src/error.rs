@@ -0,0 +1,62 @@
+use std::error;
+use std::fmt;
+
+/// Why a password could not be generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The named character set has no characters left to pick from.
+    EmptyCharset(String),
+    /// Two character sets have the same name.
+    DuplicateSetName(String),
+    /// Two character sets share characters.
+    OverlappingSets(String, String),
+    /// A setting refers to a character set that is not used.
+    UnknownSet(String),
+    /// The maximums and run limits would take too much memory and time to
+    /// keep track of.
+    TooComplex,
+    /// The length is outside of what the generator allows.
+    LengthOutOfRange { length: usize, min: usize, max: usize },
+    /// A wordlist file could not be read.
+    WordlistUnreadable(String),
+    /// A wordlist has too few words to be safe.
+    WordlistTooSmall { words: usize, min: usize },
+    /// A breach list file could not be read.
+    BreachListUnreadable(String),
+    /// A policy file could not be read or parsed.
+    PolicyUnreadable(String),
+    /// There is no profile with this name.
+    UnknownProfile(String),
+    /// The settings can not all be met at once, for example more
+    /// guaranteed characters than the password is long.
+    InfeasiblePolicy(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyCharset(set) => write!(f, "the {} set has no characters to choose from", set),
+            Error::DuplicateSetName(set) => write!(f, "there is more than one set called {}", set),
+            Error::OverlappingSets(first, second) => write!(
+                f, "the {} and {} sets have characters in common", first, second
+            ),
+            Error::LengthOutOfRange { length, min, max } => write!(
+                f, "a length of {} is not allowed, it must be between {} and {}", length, min, max
+            ),
+            Error::UnknownSet(set) => write!(f, "there is no {} set in use", set),
+            Error::TooComplex => write!(
+                f, "the maximums and run limits are too many to keep track of at this length, try fewer of them"
+            ),
+            Error::WordlistUnreadable(reason) => write!(f, "the wordlist could not be read: {}", reason),
+            Error::WordlistTooSmall { words, min } => write!(
+                f, "the wordlist has {} words that can be used, at least {} are needed", words, min
+            ),
+            Error::BreachListUnreadable(reason) => write!(f, "the breach list could not be read: {}", reason),
+            Error::PolicyUnreadable(reason) => write!(f, "the policy could not be read: {}", reason),
+            Error::UnknownProfile(name) => write!(f, "there is no profile called {}", name),
+            Error::InfeasiblePolicy(reason) => write!(f, "the settings can not be met: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {}
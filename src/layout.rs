@@ -0,0 +1,788 @@
+//! Which character set, or which character, each position of a password
+//! gets.
+
+use rand::Rng;
+
+/// A position in a password, counted from either end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// Counted from the start, `FromStart(0)` is the first character.
+    FromStart(usize),
+    /// Counted from the end, `FromEnd(0)` is the last character.
+    FromEnd(usize),
+}
+
+impl Position {
+    /// The index of this position in a password of `length` characters,
+    /// if it has one.
+    pub fn index(&self, length: usize) -> Option<usize> {
+        match *self {
+            Position::FromStart(index) if index < length => Some(index),
+            Position::FromEnd(index) if index < length => Some(length - 1 - index),
+            _ => None,
+        }
+    }
+}
+
+/// Limits on characters in a row, `None` for no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Runs {
+    /// Most identical characters in a row.
+    pub(crate) repeats: Option<usize>,
+    /// Most characters in a row that each go up by one, or each go down by
+    /// one, like "abc" or "321".
+    pub(crate) sequence: Option<usize>,
+    /// Most characters in a row from the same set.
+    pub(crate) same_set: Option<usize>,
+}
+
+impl Runs {
+    /// True if `password` has no run longer than the limits. `sets` holds
+    /// the set of each character.
+    pub(crate) fn allow(&self, password: &[char], sets: &[usize]) -> bool {
+        let too_long = |limit: Option<usize>, run: usize| limit.is_some_and(|most| run > most);
+        let mut repeats: usize = 1;
+        let mut sequence: usize = 1;
+        let mut step: i64 = 0;
+        let mut same_set: usize = 1;
+
+        for i in 1..password.len() {
+            let this_step: i64 = password[i] as i64 - password[i - 1] as i64;
+            repeats = if this_step == 0 { repeats + 1 } else { 1 };
+            sequence = match this_step {
+                1 | -1 if this_step == step => sequence + 1,
+                1 | -1 => 2,
+                _ => 1,
+            };
+            step = this_step;
+            same_set = if sets[i] == sets[i - 1] { same_set + 1 } else { 1 };
+
+            if too_long(self.repeats, repeats) || too_long(self.sequence, sequence) || too_long(self.same_set, same_set) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// True if runs of characters, not just of sets, are limited.
+    fn of_characters(&self) -> bool {
+        self.repeats.is_some() || self.sequence.is_some()
+    }
+}
+
+/// What can be picked for a position: a whole set, each of its characters
+/// being as likely, or a single character.
+struct Unit {
+    set: usize,
+    character: Option<char>,
+    ways: f64,
+}
+
+/// Counts the passwords that meet the settings, position by position, and
+/// uses those counts to pick what goes at every position.
+///
+/// While a password is built it is in one of a few states, which record how
+/// many characters of each set it has so far. A count is only followed up to
+/// the set's maximum, or up to its minimum when the maximum can not be
+/// reached anyway, so the number of states stays small. When runs are
+/// limited the state also records the last set or character and how long
+/// the runs ending there are, and single characters are picked instead of
+/// whole sets. For every position and state the table holds how many ways
+/// there are to finish the password from there. Picking with odds in
+/// proportion to those numbers makes every password that meets the settings
+/// equally likely, exactly as if random passwords were drawn until one met
+/// them, but in one pass.
+///
+/// The counts get far too big for integers, so each row of the table is
+/// scaled to keep its largest entry at 1.
+pub(crate) struct Layout {
+    sets: Vec<Vec<char>>,
+    units: Vec<Unit>,
+    /// The units of each set, from the first to one past the last. Single
+    /// characters are in code order.
+    ranges: Vec<(usize, usize)>,
+    /// For each character and set, the range of that set's characters that
+    /// are at most one away from it in code order.
+    neighbours: Vec<Vec<(usize, usize)>>,
+    /// The fewest characters of each set.
+    minimums: Vec<usize>,
+    /// Where each set's count sits in the state number.
+    radix: Vec<usize>,
+    /// How far each set's count is followed.
+    caps: Vec<usize>,
+    /// Whether the cap of each set is its maximum.
+    capped: Vec<bool>,
+    /// For each position rule, which sets are allowed there.
+    rules: Vec<(Position, Vec<bool>)>,
+    runs: Runs,
+    /// How many runs of identical or sequential characters are told apart.
+    run_lengths: usize,
+    /// How many runs of characters from the same set are told apart.
+    set_runs: usize,
+    /// How many ends of a password are told apart, the last one being the
+    /// empty password.
+    tails: usize,
+    states: usize,
+}
+
+/// Tables with more entries than this, states times positions, take too
+/// much time and memory to fill.
+pub(crate) const MAX_TABLE_SIZE: usize = 1 << 22;
+
+impl Layout {
+    /// `limits` holds the fewest and most characters of each set, and
+    /// `longest` the longest length that will be asked for.
+    pub(crate) fn new(
+        sets: &[Vec<char>],
+        limits: &[(usize, usize)],
+        rules: Vec<(Position, Vec<bool>)>,
+        runs: Runs,
+        longest: usize,
+    ) -> Option<Self> {
+        let mut radix: Vec<usize> = Vec::with_capacity(sets.len());
+        let mut caps: Vec<usize> = Vec::with_capacity(sets.len());
+        let mut capped: Vec<bool> = Vec::with_capacity(sets.len());
+        let mut counts: usize = 1;
+
+        for &(fewest, most) in limits.iter() {
+            let is_capped: bool = most < longest;
+            let cap: usize = if is_capped { most } else { fewest };
+
+            radix.push(counts);
+            caps.push(cap);
+            capped.push(is_capped);
+            counts = counts.checked_mul(cap + 1)?;
+        }
+
+        let mut units: Vec<Unit> = Vec::new();
+        let mut ranges: Vec<(usize, usize)> = Vec::with_capacity(sets.len());
+        for (set, characters) in sets.iter().enumerate() {
+            let first: usize = units.len();
+            if runs.of_characters() {
+                let mut characters: Vec<char> = characters.clone();
+                characters.sort();
+                units.extend(characters.into_iter().map(|c| Unit { set, character: Some(c), ways: 1.0 }));
+            } else {
+                units.push(Unit { set, character: None, ways: characters.len() as f64 });
+            }
+            ranges.push((first, units.len()));
+        }
+
+        let neighbours: Vec<Vec<(usize, usize)>> = units.iter()
+            .filter_map(|unit| unit.character)
+            .map(|c| {
+                ranges.iter()
+                    .map(|&(first, end)| {
+                        let near = |distance: i64| {
+                            first + units[first..end].iter()
+                                .take_while(|unit| (unit.character.unwrap() as i64 - c as i64) < distance)
+                                .count()
+                        };
+                        (near(-1), near(2))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let longer_than_one = |limit: Option<usize>| limit.map_or(0, |most| most.saturating_sub(1));
+        let run_lengths: usize = 1 + longer_than_one(runs.repeats) + 2 * longer_than_one(runs.sequence);
+        let set_runs: usize = runs.same_set.unwrap_or(1).max(1);
+        let tails: usize = if runs.of_characters() || runs.same_set.is_some() {
+            units.len().checked_mul(run_lengths)?.checked_mul(set_runs)? + 1
+        } else {
+            1
+        };
+
+        let states: usize = counts.checked_mul(tails)?;
+        if states.checked_mul(longest + 1)? > MAX_TABLE_SIZE {
+            return None;
+        }
+
+        Some(Layout {
+            sets: sets.to_vec(),
+            units,
+            ranges,
+            neighbours,
+            minimums: limits.iter().map(|limits| limits.0).collect(),
+            radix,
+            caps,
+            capped,
+            rules,
+            runs,
+            run_lengths,
+            set_runs,
+            tails,
+            states,
+        })
+    }
+
+    /// The state of the empty password.
+    fn start(&self) -> usize {
+        self.tails - 1
+    }
+
+    /// The state after `unit`, if that is allowed.
+    fn next(&self, state: usize, unit: usize) -> Option<usize> {
+        let set: usize = self.units[unit].set;
+        let counts: usize = self.next_counts(state / self.tails, set)?;
+        if self.tails == 1 {
+            return Some(counts);
+        }
+
+        let tail: usize = state % self.tails;
+        if tail == self.start() {
+            return Some(self.state_of(counts, unit, 0, 0));
+        }
+        let last: usize = tail / self.set_runs / self.run_lengths;
+        let run: usize = self.next_run(last, tail / self.set_runs % self.run_lengths, unit)?;
+        let set_run: usize = self.next_set_run(last, tail % self.set_runs, set)?;
+        Some(self.state_of(counts, unit, run, set_run))
+    }
+
+    /// The state number of these set counts and runs ending in `unit`.
+    fn state_of(&self, counts: usize, unit: usize, run: usize, set_run: usize) -> usize {
+        counts * self.tails + (unit * self.run_lengths + run) * self.set_runs + set_run
+    }
+
+    /// The set counts after a character of `set`, if that is allowed.
+    fn next_counts(&self, counts: usize, set: usize) -> Option<usize> {
+        let count: usize = counts / self.radix[set] % (self.caps[set] + 1);
+
+        if count < self.caps[set] {
+            Some(counts + self.radix[set])
+        } else if self.capped[set] {
+            None
+        } else {
+            Some(counts)
+        }
+    }
+
+    /// The run of characters from the same set after a character of `set`,
+    /// when the last unit was `last` and ended `set_run`. Runs are numbered
+    /// from 0 for a single character.
+    fn next_set_run(&self, last: usize, set_run: usize, set: usize) -> Option<usize> {
+        if self.units[last].set != set || self.runs.same_set.is_none() {
+            Some(0)
+        } else if set_run + 1 < self.set_runs {
+            Some(set_run + 1)
+        } else {
+            None
+        }
+    }
+
+    /// The run of identical or sequential characters after `unit`, when
+    /// the last unit was `last` and ended `run`. Runs are numbered 0 for a
+    /// single character, then identical runs, rising runs and falling runs,
+    /// each from two characters up.
+    fn next_run(&self, last: usize, run: usize, unit: usize) -> Option<usize> {
+        let (last, c): (char, char) = match (self.units[last].character, self.units[unit].character) {
+            (Some(last), Some(c)) => (last, c),
+            _ => return Some(0),
+        };
+        let repeats: usize = self.runs.repeats.map_or(0, |most| most.saturating_sub(1));
+        let sequence: usize = self.runs.sequence.map_or(0, |most| most.saturating_sub(1));
+
+        // Where the numbers of this kind of run start, how many there are
+        // and how long it may get.
+        let (first, count, most): (usize, usize, Option<usize>) = match c as i64 - last as i64 {
+            0 => (1, repeats, self.runs.repeats),
+            1 => (1 + repeats, sequence, self.runs.sequence),
+            -1 => (1 + repeats + sequence, sequence, self.runs.sequence),
+            _ => return Some(0),
+        };
+        let most: usize = match most {
+            Some(most) => most,
+            None => return Some(0),
+        };
+
+        let length: usize = if run >= first && run < first + count { run - first + 3 } else { 2 };
+        if length > most {
+            None
+        } else {
+            Some(first + length - 2)
+        }
+    }
+
+    /// True if a password may end in `state`.
+    fn finished(&self, state: usize) -> bool {
+        let counts: usize = state / self.tails;
+        (0..self.sets.len())
+            .all(|set| counts / self.radix[set] % (self.caps[set] + 1) >= self.minimums[set])
+    }
+
+    /// Which sets are allowed at `index`. End rules are only applied when
+    /// the `length` is known.
+    fn allowed(&self, index: usize, length: Option<usize>) -> Vec<bool> {
+        let mut allowed: Vec<bool> = vec![true; self.sets.len()];
+
+        for (position, sets) in self.rules.iter() {
+            let applies: bool = match (position, length) {
+                (Position::FromStart(start), _) => *start == index,
+                (Position::FromEnd(_), Some(length)) => position.index(length) == Some(index),
+                (Position::FromEnd(_), None) => false,
+            };
+            if applies {
+                for (allowed, &set) in allowed.iter_mut().zip(sets.iter()) {
+                    *allowed &= set;
+                }
+            }
+        }
+        allowed
+    }
+
+    /// Counts how many ways each state can be reached after one more
+    /// character at `index`, scaled so the largest is 1. Returns the counts
+    /// and the log of the scale.
+    fn forward(&self, ways: &[f64], index: usize, length: Option<usize>) -> (Vec<f64>, f64) {
+        let allowed: Vec<bool> = self.allowed(index, length);
+        if self.runs.of_characters() {
+            return scale(self.forward_characters(ways, &allowed));
+        }
+        let mut next_ways: Vec<f64> = vec![0.0; self.states];
+
+        for (state, &ways) in ways.iter().enumerate().filter(|&(_, &ways)| ways > 0.0) {
+            for unit in (0..self.units.len()).filter(|&unit| allowed[self.units[unit].set]) {
+                if let Some(next) = self.next(state, unit) {
+                    next_ways[next] += ways * self.units[unit].ways;
+                }
+            }
+        }
+        scale(next_ways)
+    }
+
+    /// `forward()` when single characters are picked. Going from a
+    /// character to one that is not next to it in code order can not start
+    /// or carry on a run of identical or sequential characters, so the ways
+    /// into each character from all of those are summed at once, in code
+    /// order from either end. Only neighbours are followed one by one.
+    fn forward_characters(&self, ways: &[f64], allowed: &[bool]) -> Vec<f64> {
+        let mut next_ways: Vec<f64> = vec![0.0; self.states];
+
+        for (state, &ways) in ways.iter().enumerate().filter(|&(_, &ways)| ways > 0.0) {
+            let tail: usize = state % self.tails;
+            let units: Vec<usize> = if tail == self.start() {
+                (0..self.units.len()).collect()
+            } else {
+                let last: usize = tail / self.set_runs / self.run_lengths;
+                self.neighbours[last].iter().flat_map(|&(near, far)| near..far).collect()
+            };
+            for unit in units.into_iter().filter(|&unit| allowed[self.units[unit].set]) {
+                if let Some(next) = self.next(state, unit) {
+                    next_ways[next] += ways;
+                }
+            }
+        }
+
+        for counts in 0..self.states / self.tails {
+            // The ways of ending in each unit with each set run, whatever
+            // the run of characters.
+            let mut last_ways: Vec<Vec<f64>> = vec![vec![0.0; self.units.len()]; self.set_runs];
+            for unit in 0..self.units.len() {
+                for run in 0..self.run_lengths {
+                    for (set_run, last_ways) in last_ways.iter_mut().enumerate() {
+                        last_ways[unit] += ways[self.state_of(counts, unit, run, set_run)];
+                    }
+                }
+            }
+            let any_run: Vec<f64> = (0..self.units.len())
+                .map(|unit| last_ways.iter().map(|ways| ways[unit]).sum())
+                .collect();
+            if any_run.iter().all(|&ways| ways == 0.0) {
+                continue;
+            }
+            let any_run: Sums = Sums::new(&any_run, &self.ranges);
+            let by_set_run: Vec<Sums> = last_ways.iter().map(|ways| Sums::new(ways, &self.ranges)).collect();
+
+            for set in (0..self.sets.len()).filter(|&set| allowed[set]) {
+                let next_counts: usize = match self.next_counts(counts, set) {
+                    Some(next_counts) => next_counts,
+                    None => continue,
+                };
+                let (first, end) = self.ranges[set];
+                for unit in first..end {
+                    for (from, &(near, far)) in self.neighbours[unit].iter().enumerate() {
+                        if from == set && self.runs.same_set.is_some() {
+                            for set_run in 1..self.set_runs {
+                                next_ways[self.state_of(next_counts, unit, 0, set_run)] +=
+                                    by_set_run[set_run - 1].outside(from, near, far);
+                            }
+                        } else {
+                            next_ways[self.state_of(next_counts, unit, 0, 0)] += any_run.outside(from, near, far);
+                        }
+                    }
+                }
+            }
+        }
+        next_ways
+    }
+
+    /// Log of the number of passwords of every length from `shortest` to
+    /// `longest`, negative infinity where there are none.
+    pub(crate) fn ln_keyspaces(&self, shortest: usize, longest: usize) -> Vec<f64> {
+        // Rules counted from the end only reach this far back.
+        let tail: usize = self.rules.iter()
+            .map(|(position, _)| match position {
+                Position::FromEnd(index) => index + 1,
+                Position::FromStart(_) => 0,
+            })
+            .max()
+            .unwrap_or(0);
+
+        // Up to `length - tail` every length sees the same rules, so the
+        // start of the count is shared. Only the rows where some length
+        // branches off are kept.
+        let mut start: Vec<f64> = vec![0.0; self.states];
+        start[self.start()] = 1.0;
+        let first: usize = shortest.saturating_sub(tail);
+        let mut shared: Vec<(Vec<f64>, f64)> = Vec::new();
+        let mut row: (Vec<f64>, f64) = (start, 0.0);
+        for index in 0..=longest.saturating_sub(tail) {
+            if index >= first {
+                shared.push(row.clone());
+            }
+            if index < longest.saturating_sub(tail) {
+                let (ways, ln_scale) = self.forward(&row.0, index, None);
+                row = (ways, row.1 + ln_scale);
+            }
+        }
+
+        (shortest..=longest)
+            .map(|length| {
+                let from: usize = length.saturating_sub(tail);
+                let (mut ways, mut ln_scale) = shared[from - first].clone();
+                for index in from..length {
+                    let (next_ways, next_scale) = self.forward(&ways, index, Some(length));
+                    ways = next_ways;
+                    ln_scale += next_scale;
+                }
+
+                let finished: f64 = ways.iter().enumerate()
+                    .filter(|&(state, _)| self.finished(state))
+                    .map(|(_, &ways)| ways)
+                    .sum();
+                finished.ln() + ln_scale
+            })
+            .collect()
+    }
+
+    /// For every index and state, how many ways there are to finish a
+    /// password of `length` characters, each row scaled.
+    fn backward(&self, length: usize) -> Vec<Vec<f64>> {
+        let mut table: Vec<Vec<f64>> = vec![Vec::new(); length + 1];
+        table[length] = (0..self.states)
+            .map(|state| if self.finished(state) { 1.0 } else { 0.0 })
+            .collect();
+
+        for index in (0..length).rev() {
+            let allowed: Vec<bool> = self.allowed(index, Some(length));
+            let ways: Vec<f64> = if self.runs.of_characters() {
+                self.backward_characters(&table[index + 1], &allowed)
+            } else {
+                (0..self.states)
+                    .map(|state| {
+                        (0..self.units.len())
+                            .filter(|&unit| allowed[self.units[unit].set])
+                            .filter_map(|unit| self.next(state, unit).map(|next| (unit, next)))
+                            .map(|(unit, next)| self.units[unit].ways * table[index + 1][next])
+                            .sum()
+                    })
+                    .collect()
+            };
+            table[index] = scale(ways).0;
+        }
+        table
+    }
+
+    /// One row of `backward()` when single characters are picked, given the
+    /// row after it. As in `forward_characters()`, the characters that are
+    /// not next to the last one are summed at once.
+    fn backward_characters(&self, next_row: &[f64], allowed: &[bool]) -> Vec<f64> {
+        // For every set count and set run, the ways to finish after each
+        // character without a run of characters.
+        let sums: Vec<Vec<Sums>> = (0..self.states / self.tails)
+            .map(|counts| {
+                (0..self.set_runs)
+                    .map(|set_run| {
+                        let ways: Vec<f64> = (0..self.units.len())
+                            .map(|unit| next_row[self.state_of(counts, unit, 0, set_run)])
+                            .collect();
+                        Sums::new(&ways, &self.ranges)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        (0..self.states)
+            .map(|state| {
+                let tail: usize = state % self.tails;
+                if tail == self.start() {
+                    return (0..self.units.len())
+                        .filter(|&unit| allowed[self.units[unit].set])
+                        .filter_map(|unit| self.next(state, unit))
+                        .map(|next| next_row[next])
+                        .sum();
+                }
+                let last: usize = tail / self.set_runs / self.run_lengths;
+                let set_run: usize = tail % self.set_runs;
+
+                let mut ways: f64 = 0.0;
+                for set in (0..self.sets.len()).filter(|&set| allowed[set]) {
+                    let (next_counts, next_set_run) = match (
+                        self.next_counts(state / self.tails, set),
+                        self.next_set_run(last, set_run, set),
+                    ) {
+                        (Some(next_counts), Some(next_set_run)) => (next_counts, next_set_run),
+                        _ => continue,
+                    };
+                    let (near, far) = self.neighbours[last][set];
+                    ways += sums[next_counts][next_set_run].outside(set, near, far);
+                    ways += (near..far)
+                        .filter_map(|unit| self.next(state, unit))
+                        .map(|next| next_row[next])
+                        .sum::<f64>();
+                }
+                ways
+            })
+            .collect()
+    }
+
+    /// The chance of each unit at `index` when the password is in `state`,
+    /// given the `table` from `backward()`.
+    fn odds(&self, table: &[Vec<f64>], index: usize, state: usize, length: usize) -> Vec<f64> {
+        let allowed: Vec<bool> = self.allowed(index, Some(length));
+        let odds: Vec<f64> = (0..self.units.len())
+            .map(|unit| match self.next(state, unit) {
+                Some(next) if allowed[self.units[unit].set] => self.units[unit].ways * table[index + 1][next],
+                _ => 0.0,
+            })
+            .collect();
+
+        let total: f64 = odds.iter().sum();
+        odds.iter().map(|odds| odds / total).collect()
+    }
+
+    /// Draws a `length` character password. There must be at least one
+    /// password of that length.
+    pub(crate) fn sample<R: Rng + ?Sized>(&self, length: usize, rng: &mut R) -> String {
+        let table: Vec<Vec<f64>> = self.backward(length);
+        let mut units: Vec<usize> = Vec::with_capacity(length);
+        let mut state: usize = self.start();
+
+        for index in 0..length {
+            let odds: Vec<f64> = self.odds(&table, index, state, length);
+            let mut roll: f64 = rng.gen::<f64>();
+
+            // Rounding can leave a sliver at the end, so fall back to the
+            // last unit that is possible at all.
+            let mut unit: usize = odds.iter().rposition(|&odds| odds > 0.0).unwrap_or(0);
+            for (i, &chance) in odds.iter().enumerate() {
+                if chance > 0.0 && roll < chance {
+                    unit = i;
+                    break;
+                }
+                roll -= chance;
+            }
+
+            units.push(unit);
+            state = self.next(state, unit).unwrap_or(state);
+        }
+
+        units.into_iter()
+            .map(|unit| {
+                let set: &[char] = &self.sets[self.units[unit].set];
+                match self.units[unit].character {
+                    Some(c) => c,
+                    None => set[rng.gen_range(0, set.len())],
+                }
+            })
+            .collect()
+    }
+
+    /// The chance that `sample()` picks exactly these units. They are sets,
+    /// or characters numbered in set order when runs of characters are
+    /// limited.
+    #[cfg(test)]
+    pub(crate) fn chance(&self, units: &[usize]) -> f64 {
+        let table: Vec<Vec<f64>> = self.backward(units.len());
+        let mut chance: f64 = 1.0;
+        let mut state: usize = self.start();
+
+        for (index, &unit) in units.iter().enumerate() {
+            chance *= self.odds(&table, index, state, units.len())[unit];
+            match self.next(state, unit) {
+                Some(next) if chance > 0.0 => state = next,
+                _ => return 0.0,
+            }
+        }
+        chance
+    }
+}
+
+/// Sums of the ways of each set's units, from the first unit up and from
+/// the last down, so the ways of all units outside a range add up quickly
+/// without taking anything away.
+struct Sums {
+    /// For each set, the sum of the units before each one and after each
+    /// one, both one longer than the set.
+    before: Vec<Vec<f64>>,
+    after: Vec<Vec<f64>>,
+    firsts: Vec<usize>,
+}
+
+impl Sums {
+    fn new(ways: &[f64], ranges: &[(usize, usize)]) -> Self {
+        let mut before: Vec<Vec<f64>> = Vec::with_capacity(ranges.len());
+        let mut after: Vec<Vec<f64>> = Vec::with_capacity(ranges.len());
+
+        for &(first, end) in ranges.iter() {
+            let mut sums: Vec<f64> = vec![0.0; end - first + 1];
+            for unit in first..end {
+                sums[unit - first + 1] = sums[unit - first] + ways[unit];
+            }
+            before.push(sums);
+
+            let mut sums: Vec<f64> = vec![0.0; end - first + 1];
+            for unit in (first..end).rev() {
+                sums[unit - first] = sums[unit - first + 1] + ways[unit];
+            }
+            after.push(sums);
+        }
+        Sums { before, after, firsts: ranges.iter().map(|range| range.0).collect() }
+    }
+
+    /// The ways of the units of `set` before `near` and from `far` on.
+    fn outside(&self, set: usize, near: usize, far: usize) -> f64 {
+        self.before[set][near - self.firsts[set]] + self.after[set][far - self.firsts[set]]
+    }
+}
+
+/// Scales `ways` so the largest is 1. Returns them with the log of the scale.
+fn scale(mut ways: Vec<f64>) -> (Vec<f64>, f64) {
+    let largest: f64 = ways.iter().cloned().fold(0.0, f64::max);
+    if largest == 0.0 {
+        return (ways, 0.0);
+    }
+    for ways in ways.iter_mut() {
+        *ways /= largest;
+    }
+    (ways, largest.ln())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sets(sets: &[&str]) -> Vec<Vec<char>> {
+        sets.iter().map(|set| set.chars().collect()).collect()
+    }
+
+    /// Whether each character is a digit, standing in for its set.
+    fn digits(password: &[char]) -> Vec<usize> {
+        password.iter().map(|c| if c.is_ascii_digit() { 1 } else { 0 }).collect()
+    }
+
+    #[test]
+    fn positions_count_from_either_end() {
+        assert_eq!(Position::FromStart(0).index(8), Some(0));
+        assert_eq!(Position::FromEnd(0).index(8), Some(7));
+        assert_eq!(Position::FromEnd(2).index(8), Some(5));
+        assert_eq!(Position::FromStart(8).index(8), None);
+    }
+
+    #[test]
+    fn keyspace_counts_every_password_that_meets_the_settings() {
+        // Two sets of 2 and 3 characters, at least one of each, 3 long:
+        // 5^3 - 2^3 - 3^3 = 90.
+        let layout = Layout::new(&sets(&["ab", "123"]), &[(1, 3), (1, 3)], Vec::new(), Runs::default(), 3).unwrap();
+        assert!((layout.ln_keyspaces(3, 3)[0] - 90f64.ln()).abs() < 1e-12);
+
+        // The same, but the first character must be from the first set:
+        // 2 * 5^2 - 2 * 2^2 = 42.
+        let rules = vec![(Position::FromStart(0), vec![true, false])];
+        let layout = Layout::new(&sets(&["ab", "123"]), &[(1, 3), (1, 3)], rules, Runs::default(), 3).unwrap();
+        assert!((layout.ln_keyspaces(3, 3)[0] - 42f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn keyspace_counts_only_passwords_without_long_runs() {
+        let sets: Vec<Vec<char>> = sets(&["abcd", "0123"]);
+        let all: Vec<char> = sets.concat();
+
+        for &runs in [
+            Runs { repeats: Some(1), ..Runs::default() },
+            Runs { repeats: Some(2), sequence: Some(2), ..Runs::default() },
+            Runs { sequence: Some(3), same_set: Some(2), ..Runs::default() },
+            Runs { same_set: Some(3), ..Runs::default() },
+        ].iter() {
+            let layout = Layout::new(&sets, &[(0, 6), (0, 6)], Vec::new(), runs, 6).unwrap();
+
+            for length in 4..=6 {
+                let mut count: usize = 0;
+                for mut index in 0..all.len().pow(length as u32) {
+                    let mut password: Vec<char> = Vec::new();
+                    for _ in 0..length {
+                        password.push(all[index % all.len()]);
+                        index /= all.len();
+                    }
+                    if runs.allow(&password, &digits(&password)) {
+                        count += 1;
+                    }
+                }
+                let counted: f64 = layout.ln_keyspaces(length, length)[0].exp();
+                assert!((counted / count as f64 - 1.0).abs() < 1e-9, "{:?}: {} != {}", runs, counted, count);
+            }
+        }
+    }
+
+    #[test]
+    fn every_password_without_long_runs_is_as_likely() {
+        let sets: Vec<Vec<char>> = sets(&["abcd", "0123"]);
+        let all: Vec<char> = sets.concat();
+        let runs = Runs { repeats: Some(1), sequence: Some(2), same_set: Some(3) };
+        let layout = Layout::new(&sets, &[(1, 4), (1, 4)], Vec::new(), runs, 4).unwrap();
+
+        let mut passwords: Vec<Vec<usize>> = Vec::new();
+        for mut index in 0..all.len().pow(4) {
+            let mut units: Vec<usize> = Vec::new();
+            for _ in 0..4 {
+                units.push(index % all.len());
+                index /= all.len();
+            }
+            let password: Vec<char> = units.iter().map(|&unit| all[unit]).collect();
+            let of: Vec<usize> = digits(&password);
+            if runs.allow(&password, &of) && of.contains(&0) && of.contains(&1) {
+                passwords.push(units);
+            } else {
+                assert_eq!(layout.chance(&units), 0.0);
+            }
+        }
+        for units in passwords.iter() {
+            let chance: f64 = layout.chance(units) * passwords.len() as f64;
+            assert!((chance - 1.0).abs() < 1e-9, "{:?}: {}", units, chance);
+        }
+    }
+
+    #[test]
+    fn runs_are_measured_by_character_and_by_set() {
+        let runs = Runs { repeats: Some(2), sequence: Some(3), same_set: Some(4) };
+        let allow = |password: &str| {
+            let password: Vec<char> = password.chars().collect();
+            runs.allow(&password, &digits(&password))
+        };
+
+        assert!(allow("aab1cba2"));
+        assert!(!allow("aaa1"));
+        assert!(!allow("abcd1"));
+        assert!(!allow("a4321"));
+        assert!(allow("abab1"));
+        assert!(!allow("abxyz"));
+    }
+
+    #[test]
+    fn too_large_tables_are_refused() {
+        let decimal: Vec<Vec<char>> = sets(&["0123456789"; 4]);
+        assert!(Layout::new(&decimal, &[(0, 40); 4], Vec::new(), Runs::default(), 100).is_none());
+        assert!(Layout::new(&decimal, &[(1, 4096); 4], Vec::new(), Runs::default(), 4096).is_some());
+    }
+}
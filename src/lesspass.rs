@@ -0,0 +1,251 @@
+//! Site passwords that are derived instead of stored, the way LessPass
+//! derives them.
+//!
+//! The master password is stretched with PBKDF2-HMAC-SHA256, 100000
+//! rounds, salted with the site, the login and the counter in hex. The 32
+//! bytes are read as one big number, and the password is its digits in the
+//! base of the characters in use: first all but one character per class
+//! from all the classes together, then one character of each class, then
+//! where to put each of those. The same inputs give the same password on
+//! any machine, and LessPass gives it too.
+
+use crate::error::Error;
+use crate::{EXTENDED_SPECIAL_CHARACTERS, LOWERCASE, UPPERCASE};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+
+/// Shortest and longest password LessPass derives.
+pub const MIN_LESSPASS_LENGTH: usize = 5;
+pub const MAX_LESSPASS_LENGTH: usize = 35;
+
+/// PBKDF2 rounds, as in LessPass.
+const ROUNDS: u32 = 100_000;
+
+/// Digits in the order LessPass uses, unlike `NUMBERS`.
+const DIGITS: &str = "0123456789";
+
+/// Settings for a derived password.
+/// Default is 16 characters from all four classes, with counter 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeriveLessPass {
+    site: String,
+    login: String,
+    counter: u32,
+    length: usize,
+    lowercase: bool,
+    uppercase: bool,
+    numbers: bool,
+    special_characters: bool,
+}
+
+impl DeriveLessPass {
+    /// Settings for the password of `login` on `site`.
+    pub fn new(site: &str, login: &str) -> Self {
+        DeriveLessPass {
+            site: site.to_string(),
+            login: login.to_string(),
+            counter: 1,
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            special_characters: true,
+        }
+    }
+
+    /// Sets the counter, raised by one to get a new password for the same
+    /// site and login.
+    pub fn counter(mut self, counter: u32) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Sets the length in characters.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Uses `LOWERCASE`, with at least one of them.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Uses `UPPERCASE`, with at least one of them.
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Uses the digits, with at least one of them.
+    pub fn numbers(mut self, numbers: bool) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Uses `EXTENDED_SPECIAL_CHARACTERS`, with at least one of them.
+    pub fn special_characters(mut self, special_characters: bool) -> Self {
+        self.special_characters = special_characters;
+        self
+    }
+
+    /// Derives the password from `master_password`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::DeriveLessPass;
+    /// let password = DeriveLessPass::new("example.org", "contact@example.org")
+    ///     .derive("password")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(password, "WHLpUL)e00[iHR+w");
+    /// ```
+    pub fn derive(&self, master_password: &str) -> Result<String, Error> {
+        let classes: Vec<&str> = self.classes()?;
+        let mut entropy: Entropy = Entropy::new(&self.stretch(master_password));
+
+        let all: Vec<char> = classes.concat().chars().collect();
+        let mut password: Vec<char> = (0..self.length - classes.len())
+            .map(|_| all[entropy.take(all.len())])
+            .collect();
+
+        let one_of_each: Vec<char> = classes.iter()
+            .map(|class| {
+                let characters: Vec<char> = class.chars().collect();
+                characters[entropy.take(characters.len())]
+            })
+            .collect();
+        for c in one_of_each {
+            let at: usize = entropy.take(password.len());
+            password.insert(at, c);
+        }
+
+        Ok(password.into_iter().collect())
+    }
+
+    /// The classes in use, in the order LessPass takes them.
+    fn classes(&self) -> Result<Vec<&'static str>, Error> {
+        if self.length < MIN_LESSPASS_LENGTH || self.length > MAX_LESSPASS_LENGTH {
+            return Err(Error::LengthOutOfRange {
+                length: self.length,
+                min: MIN_LESSPASS_LENGTH,
+                max: MAX_LESSPASS_LENGTH,
+            });
+        }
+
+        let classes: Vec<&'static str> = [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.numbers, DIGITS),
+            (self.special_characters, EXTENDED_SPECIAL_CHARACTERS),
+        ]
+            .iter()
+            .filter(|(used, _)| *used)
+            .map(|&(_, class)| class)
+            .collect();
+        if classes.is_empty() {
+            return Err(Error::InfeasiblePolicy(String::from("at least one class of characters must be used")));
+        }
+        Ok(classes)
+    }
+
+    fn stretch(&self, master_password: &str) -> [u8; 32] {
+        let salt: String = format!("{}{}{:x}", self.site, self.login, self.counter);
+        let mut key: [u8; 32] = [0; 32];
+        pbkdf2_hmac::<Sha256>(master_password.as_bytes(), salt.as_bytes(), ROUNDS, &mut key);
+        key
+    }
+}
+
+/// The derived key as a big-endian number, read one digit at a time in
+/// whatever base is asked for.
+struct Entropy {
+    words: [u32; 8],
+}
+
+impl Entropy {
+    fn new(key: &[u8; 32]) -> Self {
+        let mut words: [u32; 8] = [0; 8];
+        for (word, bytes) in words.iter_mut().zip(key.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Entropy { words }
+    }
+
+    /// Divides the number by `base` and returns the remainder.
+    fn take(&mut self, base: usize) -> usize {
+        let base: u64 = base as u64;
+        let mut remainder: u64 = 0;
+        for word in self.words.iter_mut() {
+            let value: u64 = remainder << 32 | *word as u64;
+            *word = (value / base) as u32;
+            remainder = value % base;
+        }
+        remainder as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_match_lesspass() {
+        let site = || DeriveLessPass::new("example.org", "contact@example.org");
+
+        assert_eq!(site().derive("password").unwrap(), "WHLpUL)e00[iHR+w");
+        assert_eq!(
+            site().special_characters(false).length(14).counter(2).derive("password").unwrap(),
+            "MBAsB7b1Prt8Sl"
+        );
+        assert_eq!(
+            site().lowercase(false).uppercase(false).special_characters(false).length(6).counter(3)
+                .derive("password").unwrap(),
+            "117843"
+        );
+    }
+
+    #[test]
+    fn every_class_in_use_is_in_the_password() {
+        for counter in 1..=5 {
+            let password: String = DeriveLessPass::new("example.org", "me")
+                .uppercase(false)
+                .length(5)
+                .counter(counter)
+                .derive("secret")
+                .unwrap();
+
+            assert_eq!(password.len(), 5);
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| EXTENDED_SPECIAL_CHARACTERS.contains(c)));
+            assert!(!password.chars().any(|c| c.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn settings_out_of_range_are_errors() {
+        let site = || DeriveLessPass::new("example.org", "me");
+
+        assert!(matches!(site().length(4).derive("secret"), Err(Error::LengthOutOfRange { .. })));
+        assert!(matches!(site().length(36).derive("secret"), Err(Error::LengthOutOfRange { .. })));
+        let nothing = site().lowercase(false).uppercase(false).numbers(false).special_characters(false);
+        assert!(matches!(nothing.derive("secret"), Err(Error::InfeasiblePolicy(_))));
+    }
+
+    #[test]
+    fn entropy_is_read_as_one_big_number() {
+        let mut key: [u8; 32] = [0; 32];
+        key[31] = 200;
+        key[30] = 1;
+        let mut entropy = Entropy::new(&key);
+
+        // 456 = 4 * 100 + 56
+        assert_eq!(entropy.take(100), 56);
+        assert_eq!(entropy.take(100), 4);
+        assert_eq!(entropy.take(100), 0);
+    }
+}
@@ -0,0 +1,1951 @@
+//! Random password generation.
+//!
+//! `GeneratePassword` describes which character sets a password may use and
+//! how long it should be. Call `generate()` to get a new password.
+//! `GeneratePassphrase` does the same for passphrases of random words, and
+//! `GeneratePronounceable` for passwords made of syllables, and
+//! `GeneratePin` for PINs that are not easy to guess. `check_strength()`
+//! estimates how easy a password from elsewhere is to guess, and a
+//! `BreachList` tells whether it is in a downloaded list of breached ones.
+//! `is_common_password()` does the same for the most common passwords
+//! without a download. `DeriveLessPass` derives a site's password from a
+//! master password instead of drawing it at random, and `DeriveSpectre`
+//! does it the way Spectre (Master Password) does.
+//!
+//! Settings can also be kept in TOML or JSON files as named profiles, see
+//! `PolicyFile` and `GeneratePassword::from_profile()`.
+//!
+//! Example:
+//!
+//! ```
+//! use password_generator::GeneratePassword;
+//!
+//! let password = GeneratePassword::new()
+//!     .length(24)
+//!     .lowercase(true)
+//!     .special_characters(true)
+//!     .generate();
+//!
+//! assert_eq!(password.len(), 24);
+//! ```
+
+use rand::prelude::*;
+use std::collections::BTreeMap;
+
+mod blocklist;
+// The library only reads filters and the build script only writes them.
+#[allow(dead_code)]
+mod bloom;
+mod breach;
+mod charset;
+mod entropy;
+mod error;
+mod layout;
+mod lesspass;
+mod passphrase;
+mod pin;
+mod policy;
+mod pronounceable;
+mod spectre;
+mod strength;
+mod wordlist;
+
+pub use blocklist::{is_common_password, BLOCKLIST_FALSE_POSITIVE_RATE, BLOCKLIST_PASSWORDS};
+pub use breach::BreachList;
+pub use charset::CharacterSet;
+pub use entropy::EntropyReport;
+pub use error::Error;
+pub use layout::Position;
+pub use lesspass::{DeriveLessPass, MAX_LESSPASS_LENGTH, MIN_LESSPASS_LENGTH};
+pub use passphrase::{Capitalization, GeneratePassphrase, EFF_LARGE_WORDLIST};
+pub use pin::{GeneratePin, COMMON_PINS, MAX_PIN_LENGTH, MIN_PIN_LENGTH};
+pub use policy::{Policy, PolicyFile, BUILTIN_PROFILES};
+pub use pronounceable::{GeneratePronounceable, MAX_PRONOUNCEABLE_LENGTH};
+pub use spectre::{DeriveSpectre, SpectreKey, SpectrePurpose, SpectreTemplate};
+pub use strength::{check_strength, Part, Pattern, Strength, COMMON_PASSWORDS, ENGLISH_WORDS};
+pub use wordlist::{Wordlist, MIN_WORDLIST_LENGTH};
+
+use layout::{Layout, Runs};
+
+/// Character sets.
+pub static LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+pub static UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub static NUMBERS: &str = "1234567890";
+pub static SPECIAL_CHARACTERS: &str = "!@#$%^&*()";
+
+/// All 32 ASCII punctuation characters, the OWASP list of password special
+/// characters without the space. Used in place of `SPECIAL_CHARACTERS` when
+/// `extended_special_characters()` is on.
+pub static EXTENDED_SPECIAL_CHARACTERS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Characters that are easy to mistake for one another when read aloud,
+/// handwritten or printed: zero and the letter O, one and the letters l
+/// and I, and the pipe. Left out when `unambiguous()` is on.
+pub static AMBIGUOUS_CHARACTERS: &str = "0Oo1lI|";
+
+/// Shortest password that will be generated.
+pub const MIN_LENGTH: usize = 4;
+
+/// Longest password that will be generated unless `length_limit()` says otherwise.
+pub const DEFAULT_LENGTH_LIMIT: usize = 4096;
+
+/// How many passwords are drawn before giving up on finding one that is
+/// not common or in the breach list, and how many random PINs are tried
+/// before `GeneratePin` draws one by its place among the allowed ones.
+pub const FILTER_TRIES: usize = 100;
+
+/// Settings for a password.
+/// Default is a four-character number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratePassword {
+    length: usize,
+    max_length: Option<usize>,
+    length_limit: usize,
+    lowercase: bool,
+    uppercase: bool,
+    numbers: bool,
+    special_characters: bool,
+    extended_special_characters: bool,
+    custom_sets: Vec<CharacterSet>,
+    exclude: String,
+    unambiguous: bool,
+    minimums: BTreeMap<String, usize>,
+    maximums: BTreeMap<String, usize>,
+    positions: Vec<(Position, Vec<String>)>,
+    runs: Runs,
+    breaches: Option<BreachList>,
+    reject_common: bool,
+}
+
+impl Default for GeneratePassword {
+    fn default() -> Self {
+        GeneratePassword {
+            length: 4,
+            max_length: None,
+            length_limit: DEFAULT_LENGTH_LIMIT,
+            lowercase: false,
+            uppercase: false,
+            numbers: false,
+            special_characters: false,
+            extended_special_characters: false,
+            custom_sets: Vec::new(),
+            exclude: String::new(),
+            unambiguous: false,
+            minimums: BTreeMap::new(),
+            maximums: BTreeMap::new(),
+            positions: Vec::new(),
+            runs: Runs::default(),
+            breaches: None,
+            reject_common: false,
+        }
+    }
+}
+
+impl GeneratePassword {
+    /// Same as `GeneratePassword::default()`, a four-character number.
+    pub fn new() -> Self {
+        GeneratePassword::default()
+    }
+
+    /// A password of the given length using every character set.
+    pub fn all_characters(length: usize) -> Self {
+        GeneratePassword {
+            length,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }
+    }
+
+    /// The settings of one of the profiles in `BUILTIN_PROFILES`, such as
+    /// "aws-iam", "oracle" or "wifi". Use `PolicyFile` for profiles of
+    /// your own.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::GeneratePassword;
+    /// let password = GeneratePassword::from_profile("wifi").unwrap().generate();
+    ///
+    /// assert_eq!(password.len(), 20);
+    /// ```
+    pub fn from_profile(name: &str) -> Result<Self, Error> {
+        PolicyFile::builtin().profile(name).map(Policy::generator)
+    }
+
+    /// Sets the password length. It must be at least `MIN_LENGTH` and at
+    /// most the length limit.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self.max_length = None;
+        self
+    }
+
+    /// Picks the length of each password at random, every length from
+    /// `min` to `max` (inclusive) being equally likely.
+    pub fn length_range(mut self, min: usize, max: usize) -> Self {
+        self.length = min;
+        self.max_length = Some(max);
+        self
+    }
+
+    /// Sets the longest length that is allowed, `DEFAULT_LENGTH_LIMIT` by
+    /// default. Raise it for long keys, lower it to catch typos.
+    pub fn length_limit(mut self, length_limit: usize) -> Self {
+        self.length_limit = length_limit;
+        self
+    }
+
+    /// Use lowercase letters.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Use uppercase letters.
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Use numbers.
+    pub fn numbers(mut self, numbers: bool) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Use special characters.
+    pub fn special_characters(mut self, special_characters: bool) -> Self {
+        self.special_characters = special_characters;
+        self
+    }
+
+    /// Use all 32 ASCII punctuation characters, `EXTENDED_SPECIAL_CHARACTERS`,
+    /// as the special characters instead of the 10 in `SPECIAL_CHARACTERS`.
+    /// Turns special characters on.
+    pub fn extended_special_characters(mut self, extended_special_characters: bool) -> Self {
+        self.extended_special_characters = extended_special_characters;
+        self
+    }
+
+    /// Adds a character set of your own. Like the built in sets, at least
+    /// one of its characters will be used. It may not share characters with
+    /// the other sets that are used.
+    pub fn character_set(mut self, set: CharacterSet) -> Self {
+        self.custom_sets.push(set);
+        self
+    }
+
+    /// Never uses any of these characters, whichever set they are in.
+    pub fn exclude(mut self, characters: &str) -> Self {
+        self.exclude.push_str(characters);
+        self
+    }
+
+    /// Leaves `AMBIGUOUS_CHARACTERS` out of every set, for passwords that
+    /// are read over the phone or printed.
+    pub fn unambiguous(mut self, unambiguous: bool) -> Self {
+        self.unambiguous = unambiguous;
+        self
+    }
+
+    /// Draws again while the password is in `list`. Gives up with an error
+    /// after `FILTER_TRIES` passwords, which only happens when the list
+    /// holds most of what the settings can generate.
+    pub fn breach_list(mut self, list: BreachList) -> Self {
+        self.breaches = Some(list);
+        self
+    }
+
+    /// Draws again while `is_common_password()` says the password is
+    /// common, which matters for short numbers and small sets. Gives up
+    /// with an error after `FILTER_TRIES` passwords.
+    pub fn reject_common(mut self, reject_common: bool) -> Self {
+        self.reject_common = reject_common;
+        self
+    }
+
+    /// Sets the fewest characters of the named set a password may have,
+    /// instead of one. The built in sets are called "lowercase",
+    /// "uppercase", "numbers" and "special characters"; a minimum of 0
+    /// makes a set optional.
+    pub fn minimum(mut self, set: &str, count: usize) -> Self {
+        self.minimums.insert(set.to_string(), count);
+        self
+    }
+
+    /// Sets the most characters of the named set a password may have.
+    pub fn maximum(mut self, set: &str, count: usize) -> Self {
+        self.maximums.insert(set.to_string(), count);
+        self
+    }
+
+    /// Only allows characters of the named sets at the first position,
+    /// e.g. `&["lowercase", "uppercase"]` for a password that must start
+    /// with a letter.
+    pub fn starts_with(self, sets: &[&str]) -> Self {
+        self.at(Position::FromStart(0), sets)
+    }
+
+    /// Only allows characters of the named sets at the last position.
+    pub fn ends_with(self, sets: &[&str]) -> Self {
+        self.at(Position::FromEnd(0), sets)
+    }
+
+    /// Only allows characters of the named sets at `position`. When there
+    /// are several rules for a position, a character must meet all of them.
+    pub fn at(mut self, position: Position, sets: &[&str]) -> Self {
+        self.positions.push((position, sets.iter().map(|set| set.to_string()).collect()));
+        self
+    }
+
+    /// Allows at most `count` identical characters in a row, 2 rules out
+    /// "aaa".
+    pub fn max_repeats(mut self, count: usize) -> Self {
+        self.runs.repeats = Some(count);
+        self
+    }
+
+    /// Allows at most `count` characters in a row that each go up by one,
+    /// or each go down by one, 2 rules out "abc", "123" and "cba".
+    pub fn max_sequence(mut self, count: usize) -> Self {
+        self.runs.sequence = Some(count);
+        self
+    }
+
+    /// Allows at most `count` characters in a row from the same set, 2
+    /// rules out three special characters in a row.
+    pub fn max_same_set_run(mut self, count: usize) -> Self {
+        self.runs.same_set = Some(count);
+        self
+    }
+
+    /// Checks that an existing password could have come from `generate()`:
+    /// it has the right length, only uses the wanted character sets, has
+    /// between the minimum and maximum characters from each of them and
+    /// has the right sets at the right positions and no runs that are too
+    /// long.
+    pub fn matches(&self, password: &str) -> bool {
+        let characters: Vec<char> = password.chars().collect();
+        let length: usize = characters.len();
+        if length < self.length || length > self.longest() {
+            return false;
+        }
+
+        let sets: Vec<CharacterSet> = self.sets();
+        let mut counts: Vec<usize> = vec![0; sets.len()];
+        let mut set_of: Vec<usize> = Vec::with_capacity(length);
+
+        for (index, &c) in characters.iter().enumerate() {
+            let set: usize = match sets.iter().position(|set| set.contains(c)) {
+                Some(set) => set,
+                None => return false,
+            };
+            let allowed: bool = self.positions.iter()
+                .filter(|(position, _)| position.index(length) == Some(index))
+                .all(|(_, names)| names.iter().any(|name| name == sets[set].name()));
+            if !allowed {
+                return false;
+            }
+            counts[set] += 1;
+            set_of.push(set);
+        }
+        self.runs.allow(&characters, &set_of) && counts.iter().zip(self.limits(&sets).iter())
+            .all(|(&count, &(fewest, most))| count >= fewest && count <= most)
+    }
+
+    /// Checks that a password can be generated with these settings.
+    /// Does not draw any randomness.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.layout().map(|_| ())
+    }
+
+
+    /// Generates a password(String) with the given information.
+    /// Default is a four-character number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    /// 
+    /// Example:
+    /// 
+    /// ```
+    /// # use password_generator::GeneratePassword;
+    /// let password = GeneratePassword::new()
+    ///     .length(24)
+    ///     .lowercase(true)
+    ///     .special_characters(true)
+    ///     .generate();
+    /// ```
+    pub fn generate(&self) -> String {
+        match self.try_generate() {
+            Ok(password) => password,
+            Err(error) => panic!("Failed to generate password: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but draws its randomness from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::GeneratePassword;
+    /// use rand::rngs::OsRng;
+    ///
+    /// let password = GeneratePassword::all_characters(16).generate_with(&mut OsRng);
+    /// ```
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        match self.try_generate_with(rng) {
+            Ok(password) => password,
+            Err(error) => panic!("Failed to generate password: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but returns an error instead of panicking
+    /// when the settings can not be met. The settings are checked before
+    /// any randomness is drawn.
+    pub fn try_generate(&self) -> Result<String, Error> {
+        self.try_generate_with(&mut rand::thread_rng())
+    }
+
+    /// Same as `try_generate()`, but draws its randomness from `rng`.
+    ///
+    /// The password is built in a single pass, front to back. The set of
+    /// each position is drawn with the odds it has among all passwords that
+    /// meet the settings, then a character is drawn from that set. The
+    /// result is exactly as if random passwords were drawn from all sets
+    /// until one met the settings.
+    pub fn try_generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<String, Error> {
+        let (layout, _) = self.layout()?;
+
+        if self.breaches.is_none() && !self.reject_common {
+            return Ok(layout.sample(self.draw_length(rng), rng));
+        }
+        // Each try draws its own length, so that retries stay uniform over
+        // the range.
+        for _ in 0..FILTER_TRIES {
+            let password: String = layout.sample(self.draw_length(rng), rng);
+            if !self.filtered(&password)? {
+                return Ok(password);
+            }
+        }
+        Err(Error::InfeasiblePolicy(format!("every password drawn in {} tries was common or breached", FILTER_TRIES)))
+    }
+
+    /// The length of the next password, drawn from the range when there is one.
+    fn draw_length<R: RngCore + CryptoRng>(&self, rng: &mut R) -> usize {
+        match self.max_length {
+            Some(longest) => rng.gen_range(self.length, longest + 1),
+            None => self.length,
+        }
+    }
+
+    /// Whether `password` is left out by `reject_common()` or `breach_list()`.
+    fn filtered(&self, password: &str) -> Result<bool, Error> {
+        if self.reject_common && is_common_password(password) {
+            return Ok(true);
+        }
+        match &self.breaches {
+            Some(breaches) => breaches.contains(password),
+            None => Ok(false),
+        }
+    }
+
+    /// How many bits of entropy a generated password has, counting only
+    /// the passwords `generate()` can actually return. With a length range
+    /// the choice of length adds to it.
+    pub fn entropy_bits(&self) -> Result<f64, Error> {
+        let (_, ln_keyspaces) = self.layout()?;
+
+        // Every length is equally likely and then every password of that
+        // length, so the entropy is that of the length plus the average
+        // entropy of the passwords.
+        let lengths: usize = ln_keyspaces.len();
+        let ln_passwords: f64 = ln_keyspaces.iter().sum::<f64>() / lengths as f64;
+
+        Ok((lengths as f64).log2() + ln_passwords / std::f64::consts::LN_2)
+    }
+
+    /// Counts the passwords `generate()` can return and their entropy,
+    /// and how much entropy the guarantees and other rules take away.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::GeneratePassword;
+    /// let report = GeneratePassword::new().length(8).lowercase(true).numbers(true).entropy_report().unwrap();
+    ///
+    /// // 36^8 passwords less the 26^8 without a digit and the 10^8 without a letter.
+    /// assert_eq!(report.passwords().round(), 36f64.powi(8) - 26f64.powi(8) - 10f64.powi(8));
+    /// println!("{}", report);
+    /// ```
+    pub fn entropy_report(&self) -> Result<EntropyReport, Error> {
+        let (_, ln_keyspaces) = self.layout()?;
+
+        let largest: f64 = ln_keyspaces.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let ln_passwords: f64 = largest + ln_keyspaces.iter().map(|ln| (ln - largest).exp()).sum::<f64>().ln();
+
+        let lengths: usize = ln_keyspaces.len();
+        let alphabet: usize = self.sets().iter().map(|set| set.len()).sum();
+        let average_length: f64 = (self.length + self.longest()) as f64 / 2.0;
+        let unconstrained_bits: f64 = (lengths as f64).log2() + average_length * (alphabet as f64).log2();
+
+        Ok(EntropyReport::new(ln_passwords, self.entropy_bits()?, unconstrained_bits))
+    }
+
+    /// Checks the settings and counts the passwords that meet them.
+    /// Returns the counts and the log of the number of passwords of every
+    /// length, shortest first.
+    fn layout(&self) -> Result<(Layout, Vec<f64>), Error> {
+        let sets: Vec<CharacterSet> = self.sets();
+
+        for (i, set) in sets.iter().enumerate() {
+            if set.is_empty() {
+                return Err(Error::EmptyCharset(set.name().to_string()));
+            }
+            for other in sets[..i].iter() {
+                if other.name() == set.name() {
+                    return Err(Error::DuplicateSetName(set.name().to_string()));
+                }
+                if set.characters().chars().any(|c| other.contains(c)) {
+                    return Err(Error::OverlappingSets(other.name().to_string(), set.name().to_string()));
+                }
+            }
+        }
+
+        for &length in [self.length, self.longest()].iter() {
+            if length < MIN_LENGTH || length > self.length_limit {
+                return Err(Error::LengthOutOfRange { length, min: MIN_LENGTH, max: self.length_limit });
+            }
+        }
+        if self.length > self.longest() {
+            return Err(Error::InfeasiblePolicy(format!(
+                "the shortest length ({}) is longer than the longest ({})",
+                self.length, self.longest()
+            )));
+        }
+
+        if [self.runs.repeats, self.runs.sequence, self.runs.same_set].contains(&Some(0)) {
+            return Err(Error::InfeasiblePolicy(String::from("runs must be allowed at least one character")));
+        }
+
+        for name in self.minimums.keys().chain(self.maximums.keys()) {
+            if !sets.iter().any(|set| set.name() == name) {
+                return Err(Error::UnknownSet(name.clone()));
+            }
+        }
+
+        let limits: Vec<(usize, usize)> = self.limits(&sets);
+        for (set, &(fewest, most)) in sets.iter().zip(limits.iter()) {
+            if fewest > most {
+                return Err(Error::InfeasiblePolicy(format!(
+                    "at least {} {} are wanted but at most {}", fewest, set.name(), most
+                )));
+            }
+        }
+
+        let fewest: usize = limits.iter().map(|limits| limits.0).sum();
+        if fewest > self.length {
+            return Err(Error::InfeasiblePolicy(format!(
+                "{} characters are guaranteed but the password can be only {} characters long",
+                fewest, self.length
+            )));
+        }
+        let most: usize = limits.iter().fold(0, |most: usize, limits| most.saturating_add(limits.1));
+        if most < self.longest() {
+            return Err(Error::InfeasiblePolicy(format!(
+                "at most {} characters are allowed but the password can be {} characters long",
+                most, self.longest()
+            )));
+        }
+
+        let mut rules: Vec<(Position, Vec<bool>)> = Vec::new();
+        for (position, names) in self.positions.iter() {
+            for name in names.iter() {
+                if !sets.iter().any(|set| set.name() == name) {
+                    return Err(Error::UnknownSet(name.clone()));
+                }
+            }
+            rules.push((*position, sets.iter().map(|set| names.iter().any(|name| name == set.name())).collect()));
+        }
+
+        let characters: Vec<Vec<char>> = sets.iter().map(|set| set.characters().chars().collect()).collect();
+        let layout = Layout::new(&characters, &limits, rules, self.runs, self.longest()).ok_or(Error::TooComplex)?;
+
+        let ln_keyspaces: Vec<f64> = layout.ln_keyspaces(self.length, self.longest());
+        if let Some(length) = (self.length..=self.longest()).find(|&length| ln_keyspaces[length - self.length] == f64::NEG_INFINITY) {
+            return Err(Error::InfeasiblePolicy(format!(
+                "no password of {} characters meets all the rules", length
+            )));
+        }
+        Ok((layout, ln_keyspaces))
+    }
+
+    /// The fewest and most characters of each set, in `sets` order.
+    fn limits(&self, sets: &[CharacterSet]) -> Vec<(usize, usize)> {
+        sets.iter()
+            .map(|set| (
+                self.minimums.get(set.name()).cloned().unwrap_or(1),
+                self.maximums.get(set.name()).cloned().unwrap_or(usize::MAX),
+            ))
+            .collect()
+    }
+
+    /// The longest length a password can have.
+    fn longest(&self) -> usize {
+        self.max_length.unwrap_or(self.length)
+    }
+
+    /// The character sets that will be used, in order, without the
+    /// excluded characters (and the ambiguous ones if asked to).
+    fn sets(&self) -> Vec<CharacterSet> {
+        let mut sets: Vec<CharacterSet> = Vec::new();
+
+        if self.lowercase {
+            sets.push(CharacterSet::new("lowercase", LOWERCASE));
+        }
+        if self.uppercase {
+            sets.push(CharacterSet::new("uppercase", UPPERCASE));
+        }
+        if self.numbers {
+            sets.push(CharacterSet::new("numbers", NUMBERS));
+        }
+        if self.extended_special_characters {
+            sets.push(CharacterSet::new("special characters", EXTENDED_SPECIAL_CHARACTERS));
+        } else if self.special_characters {
+            sets.push(CharacterSet::new("special characters", SPECIAL_CHARACTERS));
+        }
+        sets.extend(self.custom_sets.iter().cloned());
+
+        // Default is numbers if all sets are entered as false.
+        if sets.is_empty() {
+            sets.push(CharacterSet::new("numbers", NUMBERS));
+        }
+
+        let mut exclude: String = self.exclude.clone();
+        if self.unambiguous {
+            exclude.push_str(AMBIGUOUS_CHARACTERS);
+        }
+        sets.iter().map(|set| set.without(&exclude)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+
+    /// A random number generator that gives the same numbers every run.
+    fn seeded(seed: u64) -> ChaCha20Rng {
+        ChaCha20Rng::seed_from_u64(seed)
+    }
+
+    fn type_of<T>(_: T) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    #[test]
+    fn generated_password_is_a_string() {
+        let password = GeneratePassword {
+            ..GeneratePassword::default()
+        }.generate();
+        assert_eq!(type_of(password), "alloc::string::String");
+    }
+
+    #[test]
+    fn generated_password_contains_only_numbers_by_default() {
+        let password = GeneratePassword {
+            length: 60,
+            ..GeneratePassword::default()
+        }.generate();
+        
+        for c in password.chars() {
+            assert!(NUMBERS.contains(c));
+        }
+    }
+
+    #[test]
+    fn generated_password_is_only_four_character_long_by_default() {
+        let password = GeneratePassword {
+            ..GeneratePassword::default()
+        }.generate();
+
+        assert_eq!(password.len(), 4);
+    }
+
+    #[test]
+    fn generated_password_is_different_every_time() {
+        let mut rng = seeded(1);
+        let password = GeneratePassword { ..GeneratePassword::default() };
+        let result1 = password.generate_with(&mut rng);
+        let result2 = password.generate_with(&mut rng);
+        let result3 = password.generate_with(&mut rng);
+
+        assert_ne!(result1, result2);
+        assert_ne!(result2, result3);
+        assert_ne!(result1, result3);
+    }
+
+    #[test]
+    fn generated_password_can_be_different_lengths() {
+        let password1 = GeneratePassword {
+            length:10,
+            ..GeneratePassword::default()
+        };
+        let result1 = password1.generate();
+
+        let password2 = GeneratePassword {
+            length:15,
+            ..GeneratePassword::default()
+        };
+        let result2 = password2.generate();
+
+        let password3 = GeneratePassword {
+            length:64,
+            ..GeneratePassword::default()
+        };
+        let result3 = password3.generate();
+
+        assert_eq!(result1.len(), 10);
+        assert_eq!(result2.len(), 15);
+        assert_eq!(result3.len(), 64);
+    }
+
+    #[test]
+    fn generated_password_can_be_from_lowercase_only() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            ..GeneratePassword::default()
+        };
+        let result = password.generate();
+
+        for c in result.chars() {
+            assert!(LOWERCASE.contains(c));
+        }
+    }
+    
+    #[test]
+    fn generated_password_can_be_from_lowercase_and_numbers() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            numbers: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_numbers = false;
+        let mut has_lowercase = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_numbers, illegal_character),
+            (true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_uppercase_only() {
+        let password = GeneratePassword {
+            length: 60,
+            uppercase: true,
+            ..GeneratePassword::default()
+        }.generate();
+
+        for c in password.chars() {
+            assert!(UPPERCASE.contains(c));
+        }
+    }
+    
+    #[test]
+    fn generated_password_can_be_from_uppercase_and_numbers() {
+        let password = GeneratePassword {
+            length: 60,
+            uppercase: true,
+            numbers: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_numbers = false;
+        let mut has_uppercase = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_uppercase, has_numbers, illegal_character),
+            (true, true, false)
+        );
+    }
+    
+    #[test]
+    fn generated_password_can_be_from_uppercase_and_lowercase() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            uppercase: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_uppercase = false;
+        let mut has_lowercase = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_uppercase, illegal_character),
+            (true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_contain_lowercase_uppercase_and_numbers() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_uppercase = false;
+        let mut has_lowercase = false;
+        let mut has_numbers = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_uppercase, has_numbers, illegal_character),
+            (true, true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_special_characters, illegal_character),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_numbers_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_numbers = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_numbers, has_special_characters, illegal_character),
+            (true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_lowercase_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_lowercase = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_special_characters, illegal_character),
+            (true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_uppercase_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            uppercase: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_uppercase = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_uppercase, has_special_characters, illegal_character),
+            (true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_all_character_sets() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+        let mut has_lowercase = false;
+        let mut has_uppercase = false;
+        let mut has_numbers = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_uppercase, has_numbers, has_special_characters, illegal_character),
+            (true, true, true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_numbers_lowercase_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+
+        let mut has_lowercase = false;
+        let mut has_numbers = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_numbers, has_special_characters, illegal_character),
+            (true, true, true, false)
+        );
+    }
+
+    #[test]
+    fn generated_password_can_be_from_numbers_uppercase_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            uppercase: true,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+
+        let mut has_uppercase = false;
+        let mut has_numbers = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if NUMBERS.contains(c) {
+                has_numbers = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_uppercase, has_numbers, has_special_characters, illegal_character),
+            (true, true,  true, false)
+        );
+    }
+    
+    #[test]
+    fn generated_password_can_be_from_uppercase_lowercase_and_special_characters() {
+        let password = GeneratePassword {
+            length: 60,
+            lowercase: true,
+            uppercase: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        }.generate();
+
+        let mut has_lowercase = false;
+        let mut has_uppercase = false;
+        let mut has_special_characters = false;
+        let mut illegal_character = false;
+
+        for c in password.chars() {
+            if LOWERCASE.contains(c) {
+                has_lowercase = true;
+            } else if UPPERCASE.contains(c) {
+                has_uppercase = true;
+            } else if SPECIAL_CHARACTERS.contains(c) {
+                has_special_characters = true;
+            } else {
+                illegal_character = true;
+            }
+        }
+
+        assert_eq!(
+            (has_lowercase, has_uppercase, has_special_characters, illegal_character),
+            (true, true, true, false)
+        );
+    }
+
+    #[test]
+    fn generate_password_will_always_contain_at_least_one_character_from_each_set() {
+        let mut counter = 20;
+        while counter > 0 {
+            let password = GeneratePassword {
+                numbers: true,
+                lowercase: true,
+                uppercase: true,
+                special_characters: true,
+                ..GeneratePassword::default()
+            }.generate();
+
+            let mut has_lowercase = false;
+            let mut has_uppercase = false;
+            let mut has_numbers = false;
+            let mut has_special_characters = false;
+
+            for c in password.chars() {
+                if LOWERCASE.contains(c) {
+                    has_lowercase = true;
+                } else if UPPERCASE.contains(c) {
+                    has_uppercase = true;
+                } else if NUMBERS.contains(c) {
+                    has_numbers = true;
+                } else if SPECIAL_CHARACTERS.contains(c) {
+                    has_special_characters = true;
+                } else {
+                    continue;
+                }
+            }
+
+            assert_eq!(
+                (has_lowercase, has_uppercase, has_numbers, has_special_characters),
+                (true, true, true, true)
+            );
+            if !has_lowercase && !has_uppercase && !has_numbers && !has_special_characters {
+                break;
+            }
+            counter -= 1;
+        }
+    }
+
+    #[test]
+    fn generate_password_minimum_length_is_four() {
+        let password = GeneratePassword {
+            length: 3,
+            ..GeneratePassword::default()
+        }.try_generate();
+
+        assert_eq!(password, Err(Error::LengthOutOfRange { length: 3, min: 4, max: DEFAULT_LENGTH_LIMIT }));
+        assert_eq!(GeneratePassword::new().length(4).generate().len(), 4);
+    }
+
+    #[test]
+    fn generated_password_can_be_longer_than_255_characters() {
+        let password = GeneratePassword::all_characters(1000).generate_with(&mut seeded(3));
+
+        assert_eq!(password.chars().count(), 1000);
+    }
+
+    #[test]
+    fn length_limit_can_be_changed() {
+        let settings = GeneratePassword::new().length(5000);
+
+        assert_eq!(
+            settings.validate(),
+            Err(Error::LengthOutOfRange { length: 5000, min: 4, max: DEFAULT_LENGTH_LIMIT })
+        );
+        assert_eq!(settings.clone().length_limit(8192).validate(), Ok(()));
+        assert_eq!(
+            settings.length(20).length_limit(16).validate(),
+            Err(Error::LengthOutOfRange { length: 20, min: 4, max: 16 })
+        );
+    }
+
+    #[test]
+    fn generated_password_matches_its_settings() {
+        let settings = GeneratePassword::all_characters(30);
+        let password = settings.generate();
+
+        assert!(settings.matches(&password));
+    }
+
+    #[test]
+    fn matches_rejects_wrong_length_missing_sets_and_unwanted_characters() {
+        let settings = GeneratePassword::new().length(6).lowercase(true).numbers(true);
+
+        assert!(settings.matches("abc123"));
+        assert!(!settings.matches("abc1234"));
+        assert!(!settings.matches("abcdef"));
+        assert!(!settings.matches("abc12!"));
+        assert!(GeneratePassword::new().matches("0042"));
+    }
+
+    #[test]
+    fn common_passwords_are_drawn_again() {
+        let settings = GeneratePassword::new().length(6).reject_common(true);
+        let mut rng = seeded(22);
+        for _ in 0..2000 {
+            assert!(!is_common_password(&settings.generate_with(&mut rng)));
+        }
+
+        let common = GeneratePassword::new()
+            .character_set(CharacterSet::new("zeros", "0"))
+            .reject_common(true);
+        assert!(matches!(common.try_generate(), Err(Error::InfeasiblePolicy(_))));
+        assert_eq!(common.reject_common(false).try_generate().unwrap(), "0000");
+    }
+
+    #[test]
+    fn passwords_in_the_breach_list_are_drawn_again() {
+        use sha1::{Digest, Sha1};
+
+        let settings = GeneratePassword::new().character_set(CharacterSet::new("ab", "ab"));
+        let all: Vec<String> = (0..16)
+            .map(|bits: usize| (0..4).map(|i| if bits >> i & 1 == 1 { 'b' } else { 'a' }).collect())
+            .collect();
+        let write = |name: &str, passwords: &[String]| -> std::path::PathBuf {
+            let mut lines: Vec<String> = passwords.iter()
+                .map(|password| format!("{:X}:1\n", Sha1::digest(password.as_bytes())))
+                .collect();
+            lines.sort();
+            let path = std::env::temp_dir().join(format!("lib-breaches-{}-{}.txt", name, std::process::id()));
+            std::fs::write(&path, lines.concat()).unwrap();
+            path
+        };
+
+        let path = write("all-but-one", &all[1..]);
+        let settings = settings.breach_list(BreachList::open(&path).unwrap());
+        for seed in 0..5 {
+            assert_eq!(settings.try_generate_with(&mut seeded(seed)).unwrap(), "aaaa");
+        }
+        std::fs::remove_file(path).unwrap();
+
+        let path = write("all", &all);
+        let settings = settings.breach_list(BreachList::open(&path).unwrap());
+        assert!(matches!(settings.try_generate(), Err(Error::InfeasiblePolicy(_))));
+        // A new length is drawn for every try, so the longer passwords are found.
+        let settings = settings.length_range(4, 5);
+        for seed in 0..5 {
+            assert_eq!(settings.try_generate_with(&mut seeded(seed)).unwrap().len(), 5);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn try_generate_returns_a_password_for_valid_settings() {
+        let settings = GeneratePassword::all_characters(12);
+
+        assert_eq!(settings.validate(), Ok(()));
+        assert_eq!(settings.try_generate().map(|password| password.len()), Ok(12));
+    }
+
+    #[test]
+    fn errors_explain_what_is_wrong() {
+        let error = Error::LengthOutOfRange { length: 3, min: 4, max: 255 };
+
+        assert_eq!(
+            error.to_string(),
+            "a length of 3 is not allowed, it must be between 4 and 255"
+        );
+    }
+
+    /// Upper limit for a chi-squared statistic with `degrees` degrees of
+    /// freedom, only exceeded by chance one time in a million
+    /// (Wilson-Hilferty approximation).
+    pub(crate) fn chi_squared_limit(degrees: usize) -> f64 {
+        let z: f64 = 4.753424;
+        let k: f64 = degrees as f64;
+        k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+    }
+
+    pub(crate) fn chi_squared(observed: &[usize], expected: &[f64]) -> f64 {
+        observed.iter().zip(expected.iter())
+            .map(|(&observed, &expected)| (observed as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    /// The characters of each set, as the layout takes them.
+    fn characters(sets: &[&str]) -> Vec<Vec<char>> {
+        sets.iter().map(|set| set.chars().collect()).collect()
+    }
+
+    /// Chance of every pattern of sets, one set index per character, when
+    /// random passwords are drawn from all sets until one has between the
+    /// fewest and most characters of each set.
+    fn rejection_sampling_patterns(sizes: &[usize], limits: &[(usize, usize)], length: usize) -> Vec<(Vec<usize>, f64)> {
+        let mut patterns: Vec<(Vec<usize>, f64)> = Vec::new();
+        let mut total: f64 = 0.0;
+
+        for mut index in 0..sizes.len().pow(length as u32) {
+            let mut pattern: Vec<usize> = Vec::new();
+            for _ in 0..length {
+                pattern.push(index % sizes.len());
+                index /= sizes.len();
+            }
+            let within_limits: bool = limits.iter().enumerate().all(|(set, &(fewest, most))| {
+                let count: usize = pattern.iter().filter(|&&found| found == set).count();
+                count >= fewest && count <= most
+            });
+            if within_limits {
+                let ways: f64 = pattern.iter().map(|&set| sizes[set] as f64).product();
+                total += ways;
+                patterns.push((pattern, ways));
+            }
+        }
+        for pattern in patterns.iter_mut() {
+            pattern.1 /= total;
+        }
+        patterns
+    }
+
+    #[test]
+    fn set_patterns_have_the_same_odds_as_rejection_sampling() {
+        let sizes: Vec<usize> = vec![26, 26, 10, 10];
+        let length: usize = 7;
+        let layout = Layout::new(&characters(&[LOWERCASE, UPPERCASE, NUMBERS, SPECIAL_CHARACTERS]), &[(1, usize::MAX); 4], Vec::new(), Runs::default(), length).unwrap();
+
+        for (pattern, expected) in rejection_sampling_patterns(&sizes, &[(1, length); 4], length) {
+            let chance: f64 = layout.chance(&pattern);
+            assert!((chance / expected - 1.0).abs() < 1e-9, "{} != {}", chance, expected);
+        }
+    }
+
+    #[test]
+    fn generated_passwords_are_distributed_like_rejection_sampling() {
+        let mut rng = seeded(10);
+        let settings = GeneratePassword {
+            length: 4,
+            numbers: true,
+            special_characters: true,
+            ..GeneratePassword::default()
+        };
+        let patterns = rejection_sampling_patterns(&[10, 10], &[(1, 4); 2], 4);
+        let samples: usize = 20000;
+
+        let mut observed: Vec<usize> = vec![0; patterns.len()];
+        for _ in 0..samples {
+            let password: Vec<usize> = settings.generate_with(&mut rng).chars()
+                .map(|c| if NUMBERS.contains(c) { 0 } else { 1 })
+                .collect();
+            let found = patterns.iter().position(|(pattern, _)| *pattern == password).unwrap();
+            observed[found] += 1;
+        }
+        let expected: Vec<f64> = patterns.iter().map(|(_, chance)| chance * samples as f64).collect();
+
+        assert!(chi_squared(&observed, &expected) < chi_squared_limit(patterns.len() - 1));
+    }
+
+    #[test]
+    fn generated_password_is_always_exactly_the_requested_length() {
+        for length in 4..=255 {
+            let password = GeneratePassword::all_characters(length).generate();
+
+            assert_eq!(password.chars().count(), length);
+        }
+    }
+
+    /// Index of the set `c` belongs to, in `sets` order.
+    fn set_of(sets: &[&str], c: char) -> usize {
+        sets.iter().position(|set| set.contains(c)).unwrap()
+    }
+
+    #[test]
+    fn guaranteed_sets_appear_in_every_order_equally_often() {
+        let mut rng = seeded(11);
+        let sets: [&str; 4] = [LOWERCASE, UPPERCASE, NUMBERS, SPECIAL_CHARACTERS];
+        let samples: usize = 24000;
+
+        // With four characters and four sets every password is one
+        // character from each set, so only the order can vary.
+        let mut observed: Vec<usize> = vec![0; 256];
+        for _ in 0..samples {
+            let order: usize = GeneratePassword::all_characters(4).generate_with(&mut rng).chars()
+                .fold(0, |order, c| order * 4 + set_of(&sets, c));
+            observed[order] += 1;
+        }
+        let observed: Vec<usize> = observed.into_iter().filter(|&count| count > 0).collect();
+
+        assert_eq!(observed.len(), 24);
+        assert!(chi_squared(&observed, &[samples as f64 / 24.0; 24]) < chi_squared_limit(23));
+    }
+
+    #[test]
+    fn every_position_uses_each_set_at_the_expected_rate() {
+        let mut rng = seeded(12);
+        let sets: [&str; 4] = [LOWERCASE, UPPERCASE, NUMBERS, SPECIAL_CHARACTERS];
+        let sizes: [usize; 4] = [26, 26, 10, 10];
+        let length: usize = 8;
+        let samples: usize = 20000;
+
+        let mut observed: Vec<Vec<usize>> = vec![vec![0; sets.len()]; length];
+        for _ in 0..samples {
+            for (position, c) in GeneratePassword::all_characters(length).generate_with(&mut rng).chars().enumerate() {
+                observed[position][set_of(&sets, c)] += 1;
+            }
+        }
+
+        // The chance of each set at each position, from every pattern that
+        // rejection sampling would let through.
+        let mut expected: Vec<Vec<f64>> = vec![vec![0.0; sets.len()]; length];
+        for (pattern, chance) in rejection_sampling_patterns(&sizes, &[(1, length); 4], length) {
+            for (position, &set) in pattern.iter().enumerate() {
+                expected[position][set] += chance * samples as f64;
+            }
+        }
+
+        for (observed, expected) in observed.iter().zip(expected.iter()) {
+            assert!(chi_squared(observed, expected) < chi_squared_limit(sets.len() - 1));
+        }
+    }
+
+    #[test]
+    fn every_position_is_uniform_within_each_set() {
+        let mut rng = seeded(13);
+        let sets: [&str; 4] = [LOWERCASE, UPPERCASE, NUMBERS, SPECIAL_CHARACTERS];
+        let length: usize = 8;
+        let samples: usize = 20000;
+
+        let mut observed: Vec<Vec<Vec<usize>>> = vec![
+            sets.iter().map(|set| vec![0; set.len()]).collect();
+            length
+        ];
+        for _ in 0..samples {
+            for (position, c) in GeneratePassword::all_characters(length).generate_with(&mut rng).chars().enumerate() {
+                let set: usize = set_of(&sets, c);
+                observed[position][set][sets[set].find(c).unwrap()] += 1;
+            }
+        }
+
+        for position in observed.iter() {
+            for counts in position.iter() {
+                let total: usize = counts.iter().sum();
+                let expected: Vec<f64> = vec![total as f64 / counts.len() as f64; counts.len()];
+
+                assert!(chi_squared(counts, &expected) < chi_squared_limit(counts.len() - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn every_character_of_a_set_is_used_equally_often() {
+        let mut rng = seeded(14);
+        let length: usize = 12;
+        let samples: usize = 10000;
+
+        for (set, settings) in [
+            (LOWERCASE, GeneratePassword::new().lowercase(true).numbers(true)),
+            (UPPERCASE, GeneratePassword::new().uppercase(true).special_characters(true)),
+            (NUMBERS, GeneratePassword::new().numbers(true).lowercase(true)),
+            (SPECIAL_CHARACTERS, GeneratePassword::new().special_characters(true).uppercase(true)),
+        ].iter() {
+            let settings = settings.clone().length(length);
+            let mut observed: Vec<usize> = vec![0; set.len()];
+            for _ in 0..samples {
+                for c in settings.generate_with(&mut rng).chars() {
+                    if let Some(index) = set.find(c) {
+                        observed[index] += 1;
+                    }
+                }
+            }
+            let total: usize = observed.iter().sum();
+            let expected: Vec<f64> = vec![total as f64 / set.len() as f64; set.len()];
+
+            assert!(chi_squared(&observed, &expected) < chi_squared_limit(set.len() - 1));
+        }
+    }
+
+    #[test]
+    fn seeded_generation_is_reproducible() {
+        let settings = GeneratePassword::all_characters(16);
+
+        assert_eq!(settings.generate_with(&mut seeded(7)), settings.generate_with(&mut seeded(7)));
+        assert_ne!(settings.generate_with(&mut seeded(7)), settings.generate_with(&mut seeded(8)));
+    }
+
+    #[test]
+    fn seeded_generation_gives_known_passwords() {
+        let mut rng = seeded(42);
+
+        assert_eq!(GeneratePassword::new().generate_with(&mut rng), "4952");
+        assert_eq!(GeneratePassword::all_characters(16).generate_with(&mut rng), "9&ROguKhNwFY6N6p");
+        assert_eq!(
+            GeneratePassword::new().length(10).lowercase(true).numbers(true).generate_with(&mut rng),
+            "asut8essz5"
+        );
+    }
+
+    #[test]
+    fn generated_password_length_can_vary_within_a_range() {
+        let mut rng = seeded(20);
+        let settings = GeneratePassword::all_characters(8).length_range(8, 11);
+        let samples: usize = 8000;
+
+        let mut observed: Vec<usize> = vec![0; 4];
+        for _ in 0..samples {
+            let password = settings.generate_with(&mut rng);
+            assert!(settings.matches(&password));
+            observed[password.chars().count() - 8] += 1;
+        }
+
+        assert!(chi_squared(&observed, &[samples as f64 / 4.0; 4]) < chi_squared_limit(3));
+    }
+
+    #[test]
+    fn length_range_must_be_in_order_and_within_the_limits() {
+        assert!(matches!(
+            GeneratePassword::new().length_range(12, 8).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert_eq!(
+            GeneratePassword::new().length_range(8, 5000).validate(),
+            Err(Error::LengthOutOfRange { length: 5000, min: 4, max: DEFAULT_LENGTH_LIMIT })
+        );
+        assert_eq!(GeneratePassword::new().length_range(8, 8).validate(), Ok(()));
+    }
+
+    #[test]
+    fn entropy_counts_only_passwords_that_can_be_generated() {
+        // 10^4 four-digit numbers.
+        let bits: f64 = GeneratePassword::new().entropy_bits().unwrap();
+        assert!((bits - 10_000f64.log2()).abs() < 1e-9);
+
+        // 20^4 passwords, less the 10^4 with only numbers and the 10^4
+        // with only special characters.
+        let bits: f64 = GeneratePassword::new().numbers(true).special_characters(true).entropy_bits().unwrap();
+        assert!((bits - 140_000f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn entropy_of_a_length_range_adds_the_choice_of_length() {
+        let bits: f64 = GeneratePassword::new().length_range(4, 7).entropy_bits().unwrap();
+        let expected: f64 = 2.0 + (4.0 + 5.0 + 6.0 + 7.0) / 4.0 * 10f64.log2();
+
+        assert!((bits - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn generated_password_can_use_a_custom_set() {
+        let mut rng = seeded(30);
+        let settings = GeneratePassword::new()
+            .length(10)
+            .lowercase(true)
+            .character_set(CharacterSet::new("safe symbols", "-_."));
+
+        for _ in 0..50 {
+            let password = settings.generate_with(&mut rng);
+
+            assert!(password.chars().all(|c| LOWERCASE.contains(c) || "-_.".contains(c)));
+            assert!(password.chars().any(|c| "-_.".contains(c)));
+            assert!(password.chars().any(|c| LOWERCASE.contains(c)));
+        }
+    }
+
+    #[test]
+    fn custom_sets_alone_replace_the_default_numbers() {
+        let password = GeneratePassword::new()
+            .length(30)
+            .character_set(CharacterSet::new("hex", "0123456789abcdef"))
+            .generate_with(&mut seeded(31));
+
+        assert!(password.chars().any(|c| "abcdef".contains(c)));
+    }
+
+    #[test]
+    fn excluded_characters_are_never_used() {
+        let mut rng = seeded(32);
+        let settings = GeneratePassword::all_characters(40).exclude("&%^");
+
+        for _ in 0..50 {
+            let password = settings.generate_with(&mut rng);
+
+            assert!(!password.contains(&['&', '%', '^'][..]));
+            assert!(settings.matches(&password));
+        }
+        assert!(!settings.matches(&"a&B1".repeat(10)));
+    }
+
+    #[test]
+    fn sets_must_have_characters_left_and_not_overlap() {
+        assert_eq!(
+            GeneratePassword::new().special_characters(true).exclude(SPECIAL_CHARACTERS).validate(),
+            Err(Error::EmptyCharset(String::from("special characters")))
+        );
+        assert_eq!(
+            GeneratePassword::new().lowercase(true)
+                .character_set(CharacterSet::new("vowels", "aeiou"))
+                .validate(),
+            Err(Error::OverlappingSets(String::from("lowercase"), String::from("vowels")))
+        );
+        assert_eq!(
+            GeneratePassword::new()
+                .character_set(CharacterSet::new("hex", "abcdef"))
+                .character_set(CharacterSet::new("hex", "ABCDEF"))
+                .validate(),
+            Err(Error::DuplicateSetName(String::from("hex")))
+        );
+    }
+
+    #[test]
+    fn entropy_uses_what_is_left_of_the_sets() {
+        // Three numbers left, each of the four characters is one of them.
+        let bits: f64 = GeneratePassword::new().exclude("0123456").entropy_bits().unwrap();
+
+        assert!((bits - 4.0 * 3f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn extended_special_characters_are_all_ascii_punctuation() {
+        assert_eq!(EXTENDED_SPECIAL_CHARACTERS.len(), 32);
+        assert!(EXTENDED_SPECIAL_CHARACTERS.chars().all(|c| c.is_ascii_punctuation()));
+        assert!(SPECIAL_CHARACTERS.chars().all(|c| EXTENDED_SPECIAL_CHARACTERS.contains(c)));
+    }
+
+    #[test]
+    fn generated_password_can_use_extended_special_characters() {
+        let mut rng = seeded(40);
+        let settings = GeneratePassword::new().length(60).extended_special_characters(true);
+
+        let mut found: String = String::new();
+        for _ in 0..20 {
+            for c in settings.generate_with(&mut rng).chars() {
+                assert!(EXTENDED_SPECIAL_CHARACTERS.contains(c));
+                if !found.contains(c) {
+                    found.push(c);
+                }
+            }
+        }
+
+        assert_eq!(found.len(), 32);
+    }
+
+    #[test]
+    fn extended_special_characters_add_entropy() {
+        let basic: f64 = GeneratePassword::all_characters(16).entropy_bits().unwrap();
+        let extended: f64 = GeneratePassword::all_characters(16)
+            .extended_special_characters(true)
+            .entropy_bits()
+            .unwrap();
+
+        assert!(extended > basic + 2.0);
+    }
+
+    #[test]
+    fn unambiguous_passwords_have_no_look_alike_characters() {
+        let mut rng = seeded(50);
+        let settings = GeneratePassword::all_characters(40).unambiguous(true);
+
+        for _ in 0..50 {
+            let password = settings.generate_with(&mut rng);
+
+            assert!(password.chars().all(|c| !AMBIGUOUS_CHARACTERS.contains(c)));
+            assert!(settings.matches(&password));
+        }
+    }
+
+    #[test]
+    fn unambiguous_entropy_uses_the_smaller_sets() {
+        // Eight numbers are left without 0 and 1.
+        let bits: f64 = GeneratePassword::new().unambiguous(true).entropy_bits().unwrap();
+        assert!((bits - 4.0 * 8f64.log2()).abs() < 1e-9);
+
+        assert!(
+            GeneratePassword::all_characters(16).unambiguous(true).entropy_bits().unwrap()
+                < GeneratePassword::all_characters(16).entropy_bits().unwrap()
+        );
+    }
+
+    #[test]
+    fn set_patterns_within_limits_have_the_same_odds_as_rejection_sampling() {
+        let sizes: Vec<usize> = vec![26, 10, 10];
+        let limits: Vec<(usize, usize)> = vec![(0, 8), (2, 8), (2, 3)];
+        let length: usize = 8;
+        let layout = Layout::new(&characters(&[LOWERCASE, NUMBERS, SPECIAL_CHARACTERS]), &limits, Vec::new(), Runs::default(), length).unwrap();
+
+        for (pattern, expected) in rejection_sampling_patterns(&sizes, &limits, length) {
+            let chance: f64 = layout.chance(&pattern);
+            assert!((chance / expected - 1.0).abs() < 1e-9, "{} != {}", chance, expected);
+        }
+    }
+
+    #[test]
+    fn generated_password_has_the_minimum_and_maximum_of_each_set() {
+        let mut rng = seeded(60);
+        let settings = GeneratePassword::all_characters(12)
+            .minimum("numbers", 2)
+            .minimum("special characters", 2)
+            .maximum("special characters", 4)
+            .minimum("uppercase", 0);
+
+        let mut without_uppercase: bool = false;
+        for _ in 0..500 {
+            let password = settings.generate_with(&mut rng);
+            let numbers: usize = password.chars().filter(|&c| NUMBERS.contains(c)).count();
+            let special: usize = password.chars().filter(|&c| SPECIAL_CHARACTERS.contains(c)).count();
+
+            assert!(numbers >= 2);
+            assert!((2..=4).contains(&special));
+            assert!(settings.matches(&password));
+            without_uppercase |= !password.chars().any(|c| UPPERCASE.contains(c));
+        }
+        assert!(without_uppercase);
+    }
+
+    #[test]
+    fn impossible_minimums_and_maximums_are_errors() {
+        assert!(matches!(
+            GeneratePassword::all_characters(8).minimum("numbers", 3).minimum("uppercase", 4).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert!(matches!(
+            GeneratePassword::all_characters(8).minimum("numbers", 3).maximum("numbers", 2).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert!(matches!(
+            GeneratePassword::new().length(5).maximum("numbers", 4).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert_eq!(
+            GeneratePassword::new().minimum("lowercase", 2).validate(),
+            Err(Error::UnknownSet(String::from("lowercase")))
+        );
+        assert_eq!(
+            GeneratePassword::all_characters(8).minimum("numbers", 3).minimum("uppercase", 3).validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn minimums_and_maximums_shrink_the_entropy() {
+        // Two numbers and two special characters, in any order.
+        let bits: f64 = GeneratePassword::new()
+            .numbers(true)
+            .special_characters(true)
+            .maximum("numbers", 2)
+            .maximum("special characters", 2)
+            .entropy_bits()
+            .unwrap();
+
+        assert!((bits - (6.0 * 10_000.0f64).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn set_patterns_with_position_rules_have_the_same_odds_as_rejection_sampling() {
+        let sizes: Vec<usize> = vec![26, 10, 10];
+        let limits: Vec<(usize, usize)> = vec![(1, 6), (1, 6), (1, 2)];
+        let length: usize = 6;
+        let rules = vec![
+            (Position::FromStart(0), vec![true, false, false]),
+            (Position::FromEnd(0), vec![true, true, false]),
+            (Position::FromEnd(2), vec![false, true, true]),
+        ];
+        let layout = Layout::new(&characters(&[LOWERCASE, NUMBERS, SPECIAL_CHARACTERS]), &limits, rules.clone(), Runs::default(), length).unwrap();
+
+        // Rejection sampling that also throws away passwords breaking a rule.
+        let patterns: Vec<(Vec<usize>, f64)> = rejection_sampling_patterns(&sizes, &limits, length).into_iter()
+            .filter(|(pattern, _)| rules.iter().all(|(position, sets)| sets[pattern[position.index(length).unwrap()]]))
+            .collect();
+        let total: f64 = patterns.iter().map(|(_, chance)| chance).sum();
+
+        for (pattern, chance) in patterns.iter() {
+            let expected: f64 = chance / total;
+            let chance: f64 = layout.chance(pattern);
+            assert!((chance / expected - 1.0).abs() < 1e-9, "{} != {}", chance, expected);
+        }
+        assert_eq!(layout.chance(&[1, 0, 0, 1, 0, 0]), 0.0);
+    }
+
+    #[test]
+    fn generated_password_follows_the_position_rules() {
+        let mut rng = seeded(14);
+        let settings = GeneratePassword::all_characters(12)
+            .starts_with(&["lowercase", "uppercase"])
+            .ends_with(&["numbers"])
+            .at(Position::FromStart(3), &["special characters"]);
+
+        for _ in 0..1000 {
+            let password: Vec<char> = settings.generate_with(&mut rng).chars().collect();
+
+            assert!(password[0].is_ascii_alphabetic());
+            assert!(NUMBERS.contains(password[11]));
+            assert!(SPECIAL_CHARACTERS.contains(password[3]));
+        }
+        assert!(settings.matches("aB1!cdefgh#2"));
+        assert!(!settings.matches("1Ba!cdefgh#2"));
+        assert!(!settings.matches("aB1!cdefgh#b"));
+    }
+
+    #[test]
+    fn impossible_position_rules_are_errors() {
+        assert_eq!(
+            GeneratePassword::new().starts_with(&["lowercase"]).validate(),
+            Err(Error::UnknownSet(String::from("lowercase")))
+        );
+        assert!(matches!(
+            GeneratePassword::all_characters(8)
+                .starts_with(&["numbers"])
+                .ends_with(&["numbers"])
+                .maximum("numbers", 1)
+                .validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert!(matches!(
+            GeneratePassword::all_characters(8).starts_with(&[]).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+    }
+
+    #[test]
+    fn position_rules_shrink_the_entropy() {
+        // A lowercase letter, then anything that still gets a number in.
+        let bits: f64 = GeneratePassword::new()
+            .lowercase(true)
+            .numbers(true)
+            .starts_with(&["lowercase"])
+            .entropy_bits()
+            .unwrap();
+
+        assert!((bits - (26.0 * (36.0f64.powi(3) - 26.0f64.powi(3))).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn long_passwords_with_position_rules_are_quick() {
+        let settings = GeneratePassword::all_characters(DEFAULT_LENGTH_LIMIT)
+            .length_range(MIN_LENGTH, DEFAULT_LENGTH_LIMIT)
+            .starts_with(&["lowercase"])
+            .ends_with(&["special characters"]);
+
+        assert!(settings.entropy_bits().unwrap() > 0.0);
+        assert!(settings.matches(&settings.generate()));
+    }
+
+    #[test]
+    fn generated_password_has_no_long_runs() {
+        let mut rng = seeded(15);
+        let settings = GeneratePassword::all_characters(16)
+            .max_repeats(1)
+            .max_sequence(2)
+            .max_same_set_run(2);
+        let sets: [&str; 4] = [LOWERCASE, UPPERCASE, NUMBERS, SPECIAL_CHARACTERS];
+
+        for _ in 0..50 {
+            let password: Vec<char> = settings.generate_with(&mut rng).chars().collect();
+
+            for window in password.windows(2) {
+                assert_ne!(window[0], window[1]);
+            }
+            for window in password.windows(3) {
+                let steps: (i64, i64) = (window[1] as i64 - window[0] as i64, window[2] as i64 - window[1] as i64);
+                assert!(!(steps == (1, 1) || steps == (-1, -1)), "{:?}", window);
+                assert!(!(set_of(&sets, window[0]) == set_of(&sets, window[1]) && set_of(&sets, window[1]) == set_of(&sets, window[2])));
+            }
+        }
+        assert!(!settings.matches("aB1!cdeF2@gH3#iJ"));
+        assert!(!settings.matches("aB159!cD2@eF3#gH"));
+        assert!(settings.matches("aB1!cD2@eF3#gH4$"));
+    }
+
+    #[test]
+    fn impossible_runs_are_errors() {
+        assert!(matches!(
+            GeneratePassword::new().length(6).max_same_set_run(3).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert!(matches!(
+            GeneratePassword::new().max_repeats(0).validate(),
+            Err(Error::InfeasiblePolicy(_))
+        ));
+        assert_eq!(GeneratePassword::new().length(8).max_repeats(1).max_sequence(1).validate(), Ok(()));
+    }
+
+    #[test]
+    fn runs_shrink_the_entropy() {
+        // Four digits, no two the same in a row: 10 * 9 * 9 * 9.
+        let bits: f64 = GeneratePassword::new().max_repeats(1).entropy_bits().unwrap();
+        assert!((bits - (10.0 * 729.0f64).log2()).abs() < 1e-9);
+
+        // Letters and numbers taking turns, one of each at least.
+        let bits: f64 = GeneratePassword::new()
+            .lowercase(true)
+            .numbers(true)
+            .max_same_set_run(1)
+            .entropy_bits()
+            .unwrap();
+        assert!((bits - (2.0 * 26.0 * 10.0 * 26.0 * 10.0f64).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn entropy_report_counts_the_passwords_exactly() {
+        // Letters and digits with at least one of each.
+        let report = GeneratePassword::new().length(6).lowercase(true).numbers(true).entropy_report().unwrap();
+        let passwords: f64 = 36f64.powi(6) - 26f64.powi(6) - 10f64.powi(6);
+
+        assert_eq!(report.passwords().round(), passwords);
+        assert!((report.bits() - passwords.log2()).abs() < 1e-9);
+        assert!((report.unconstrained_bits() - 6.0 * 36f64.log2()).abs() < 1e-9);
+        assert!((report.bits_lost() - (36f64.powi(6) / passwords).log2()).abs() < 1e-9);
+
+        // Every length of a range adds its passwords.
+        let report = GeneratePassword::new().length_range(4, 5).entropy_report().unwrap();
+        assert_eq!(report.passwords().round(), 110_000.0);
+        assert!((report.bits() - (1.0 + 4.5 * 10f64.log2())).abs() < 1e-9);
+        assert!(report.bits_lost() < 1e-9);
+    }
+
+    #[test]
+    fn entropy_report_of_long_passwords_does_not_overflow() {
+        let report = GeneratePassword::all_characters(400).entropy_report().unwrap();
+
+        assert!(report.passwords().is_infinite());
+        assert!((report.log10_passwords() - report.bits() * 2f64.log10()).abs() < 1e-6);
+        assert!(report.to_string().contains(&format!("e{} possible passwords", report.log10_passwords().floor())));
+    }
+
+    #[test]
+    fn generated_passwords_without_runs_are_distributed_like_rejection_sampling() {
+        let mut rng = seeded(16);
+        let settings = GeneratePassword::new()
+            .max_repeats(1)
+            .max_sequence(2)
+            .exclude("456789");
+        let samples: usize = 20000;
+
+        // Every password of four of 0, 1, 2 and 3 that rejection sampling
+        // would keep.
+        let digits: Vec<char> = "0123".chars().collect();
+        let mut expected: Vec<String> = Vec::new();
+        for index in 0..256usize {
+            let password: String = (0..4).map(|i| digits[index >> (2 * i) & 3]).collect();
+            if settings.matches(&password) {
+                expected.push(password);
+            }
+        }
+
+        let mut observed: Vec<usize> = vec![0; expected.len()];
+        for _ in 0..samples {
+            let password: String = settings.generate_with(&mut rng);
+            observed[expected.iter().position(|expected| *expected == password).unwrap()] += 1;
+        }
+        let chance: f64 = samples as f64 / expected.len() as f64;
+
+        assert!(chi_squared(&observed, &vec![chance; expected.len()]) < chi_squared_limit(expected.len() - 1));
+    }
+}
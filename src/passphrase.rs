@@ -0,0 +1,344 @@
+//! Passphrases made of random words, Diceware style.
+
+use crate::error::Error;
+use crate::wordlist::Wordlist;
+use crate::{NUMBERS, SPECIAL_CHARACTERS};
+use rand::prelude::*;
+use std::borrow::Cow;
+
+/// The EFF long wordlist: 7776 words, one per line, so each word is worth
+/// log2(7776), about 12.9 bits. Published by the Electronic Frontier
+/// Foundation under CC BY 3.0.
+pub static EFF_LARGE_WORDLIST: &str = include_str!("wordlists/eff_large.txt");
+
+/// How the words of a passphrase are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capitalization {
+    /// all lowercase, as the wordlist keeps them.
+    Lowercase,
+    /// ALL UPPERCASE.
+    Uppercase,
+    /// The first letter of every word uppercase.
+    Title,
+}
+
+/// Settings for a passphrase.
+/// Default is six lowercase words from the EFF long wordlist joined by "-".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratePassphrase {
+    wordlist: Wordlist,
+    words: usize,
+    separator: String,
+    capitalization: Capitalization,
+    digit: bool,
+    special_character: bool,
+}
+
+impl Default for GeneratePassphrase {
+    fn default() -> Self {
+        GeneratePassphrase {
+            wordlist: Wordlist::default(),
+            words: 6,
+            separator: String::from("-"),
+            capitalization: Capitalization::Lowercase,
+            digit: false,
+            special_character: false,
+        }
+    }
+}
+
+impl GeneratePassphrase {
+    /// Same as `GeneratePassphrase::default()`, six words.
+    pub fn new() -> Self {
+        GeneratePassphrase::default()
+    }
+
+    /// Picks the words from `wordlist` instead of the EFF long wordlist.
+    pub fn wordlist(mut self, wordlist: Wordlist) -> Self {
+        self.wordlist = wordlist;
+        self
+    }
+
+    /// Sets the number of words, at least one.
+    pub fn words(mut self, words: usize) -> Self {
+        self.words = words;
+        self
+    }
+
+    /// Sets what goes between the words. Words that contain it are left
+    /// out of the list, and when it is empty, words that start other words
+    /// are, so that no passphrase can be made in two ways.
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Sets how the words are written.
+    pub fn capitalization(mut self, capitalization: Capitalization) -> Self {
+        self.capitalization = capitalization;
+        self
+    }
+
+    /// Adds a random digit from `NUMBERS` at the end, for policies that
+    /// want one.
+    pub fn digit(mut self, digit: bool) -> Self {
+        self.digit = digit;
+        self
+    }
+
+    /// Adds a random character from `SPECIAL_CHARACTERS` at the end, after
+    /// the digit if there is one.
+    pub fn special_character(mut self, special_character: bool) -> Self {
+        self.special_character = special_character;
+        self
+    }
+
+    /// Checks that a passphrase can be generated with these settings.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.words == 0 {
+            return Err(Error::InfeasiblePolicy(String::from("a passphrase needs at least one word")));
+        }
+        self.usable_words().check_length()
+    }
+
+    /// Generates a passphrase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::{Capitalization, GeneratePassphrase};
+    /// let passphrase = GeneratePassphrase::new()
+    ///     .words(5)
+    ///     .separator(" ")
+    ///     .capitalization(Capitalization::Title)
+    ///     .digit(true)
+    ///     .generate();
+    ///
+    /// assert_eq!(passphrase.split(' ').count(), 5);
+    /// ```
+    pub fn generate(&self) -> String {
+        match self.try_generate() {
+            Ok(passphrase) => passphrase,
+            Err(error) => panic!("Failed to generate passphrase: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but draws its randomness from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        match self.try_generate_with(rng) {
+            Ok(passphrase) => passphrase,
+            Err(error) => panic!("Failed to generate passphrase: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but returns an error instead of panicking
+    /// when the settings can not be met.
+    pub fn try_generate(&self) -> Result<String, Error> {
+        self.try_generate_with(&mut rand::thread_rng())
+    }
+
+    /// Same as `try_generate()`, but draws its randomness from `rng`.
+    /// Every word is picked from the whole list, each as likely.
+    pub fn try_generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<String, Error> {
+        self.validate()?;
+
+        let wordlist: Cow<'_, Wordlist> = self.usable_words();
+        let words: Vec<String> = (0..self.words)
+            .map(|_| self.capitalize(wordlist.words().choose(rng).unwrap()))
+            .collect();
+        let mut passphrase: String = words.join(&self.separator);
+
+        if self.digit {
+            passphrase.push(*NUMBERS.chars().collect::<Vec<char>>().choose(rng).unwrap());
+        }
+        if self.special_character {
+            passphrase.push(*SPECIAL_CHARACTERS.chars().collect::<Vec<char>>().choose(rng).unwrap());
+        }
+        Ok(passphrase)
+    }
+
+    /// How many bits of entropy each word adds, counting only the words
+    /// that can be used with the separator.
+    pub fn bits_per_word(&self) -> Result<f64, Error> {
+        self.validate()?;
+        Ok(self.usable_words().bits_per_word())
+    }
+
+    /// How many bits of entropy a generated passphrase has: the bits of
+    /// every word plus those of the digit and special character.
+    pub fn entropy_bits(&self) -> Result<f64, Error> {
+        let mut bits: f64 = self.words as f64 * self.bits_per_word()?;
+        if self.digit {
+            bits += (NUMBERS.len() as f64).log2();
+        }
+        if self.special_character {
+            bits += (SPECIAL_CHARACTERS.len() as f64).log2();
+        }
+        Ok(bits)
+    }
+
+    /// The words that can be used with the separator.
+    fn usable_words(&self) -> Cow<'_, Wordlist> {
+        if self.separator.is_empty() {
+            if self.wordlist.prefixes().is_empty() {
+                Cow::Borrowed(&self.wordlist)
+            } else {
+                Cow::Owned(self.wordlist.prefix_free())
+            }
+        } else if self.wordlist.words().iter().any(|word| word.contains(self.separator.as_str())) {
+            // "drop-down" and "t-shirt" would read as two words with "-".
+            Cow::Owned(self.wordlist.without(&self.separator))
+        } else {
+            Cow::Borrowed(&self.wordlist)
+        }
+    }
+
+    /// Writes `word` the way the settings ask for.
+    fn capitalize(&self, word: &str) -> String {
+        match self.capitalization {
+            Capitalization::Lowercase => word.to_lowercase(),
+            Capitalization::Uppercase => word.to_uppercase(),
+            Capitalization::Title => {
+                let mut characters = word.chars();
+                match characters.next() {
+                    Some(first) => first.to_uppercase().chain(characters).collect(),
+                    None => String::new(),
+                }
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+
+    fn seeded(seed: u64) -> ChaCha20Rng {
+        ChaCha20Rng::seed_from_u64(seed)
+    }
+
+    #[test]
+    fn wordlist_has_7776_different_lowercase_words() {
+        let mut words: Vec<&str> = EFF_LARGE_WORDLIST.lines().collect();
+        words.sort();
+        words.dedup();
+
+        assert_eq!(words.len(), 7776);
+        assert!(words.iter().all(|word| word.chars().all(|c| c.is_ascii_lowercase() || c == '-')));
+    }
+
+    #[test]
+    fn default_passphrase_is_six_words_from_the_list() {
+        let passphrase = GeneratePassphrase::new().separator(" ").generate();
+        let words: Vec<&str> = passphrase.split(' ').collect();
+
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|word| EFF_LARGE_WORDLIST.lines().any(|listed| listed == *word)));
+    }
+
+    #[test]
+    fn passphrase_follows_the_settings() {
+        let mut rng = seeded(1);
+        let passphrase = GeneratePassphrase::new()
+            .words(4)
+            .separator(" ")
+            .capitalization(Capitalization::Title)
+            .digit(true)
+            .special_character(true)
+            .generate_with(&mut rng);
+        let words: Vec<&str> = passphrase.split(' ').collect();
+
+        assert_eq!(words.len(), 4);
+        assert!(words.iter().all(|word| word.chars().next().unwrap().is_ascii_uppercase()));
+        let mut end = passphrase.chars().rev();
+        assert!(SPECIAL_CHARACTERS.contains(end.next().unwrap()));
+        assert!(NUMBERS.contains(end.next().unwrap()));
+    }
+
+    #[test]
+    fn words_can_be_uppercase() {
+        let passphrase = GeneratePassphrase::new()
+            .capitalization(Capitalization::Uppercase)
+            .separator("")
+            .generate();
+
+        assert!(passphrase.chars().all(|c| c.is_ascii_uppercase() || c == '-'));
+    }
+
+    #[test]
+    fn seeded_generation_gives_known_passphrases() {
+        let mut rng = seeded(42);
+
+        assert_eq!(GeneratePassphrase::new().words(3).generate_with(&mut rng), "superjet-mothball-pummel");
+    }
+
+    #[test]
+    fn entropy_adds_up_the_words_and_extras() {
+        let bits: f64 = GeneratePassphrase::new().separator(" ").entropy_bits().unwrap();
+        assert!((bits - 6.0 * 7776f64.log2()).abs() < 1e-9);
+
+        let bits: f64 = GeneratePassphrase::new().words(4).digit(true).special_character(true).entropy_bits().unwrap();
+        assert!((bits - 4.0 * 7772f64.log2() - 2.0 * 10f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn words_can_come_from_another_list() {
+        let text: String = (0..2000).map(|i| format!("wort{}\n", i)).collect();
+        let settings = GeneratePassphrase::new().wordlist(Wordlist::parse(&text).unwrap()).separator(" ");
+
+        assert!(settings.generate().split(' ').all(|word| word.starts_with("wort")));
+        assert!((settings.entropy_bits().unwrap() - 6.0 * 2000f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn words_in_another_case_are_only_counted_once() {
+        let text: String = (0..2000).map(|i| format!("Wort{}\nwort{}\n", i, i)).collect();
+        let settings = GeneratePassphrase::new().wordlist(Wordlist::parse(&text).unwrap()).separator(" ");
+
+        assert!((settings.bits_per_word().unwrap() - 2000f64.log2()).abs() < 1e-9);
+        assert!(settings.generate().split(' ').all(|word| word.starts_with("wort")));
+        let title = settings.capitalization(Capitalization::Title);
+        assert!(title.generate().split(' ').all(|word| word.starts_with("Wort")));
+    }
+
+    #[test]
+    fn words_with_the_separator_in_them_are_left_out() {
+        // "drop-down", "felt-tip", "t-shirt" and "yo-yo".
+        let settings = GeneratePassphrase::new().words(200);
+
+        assert!((settings.bits_per_word().unwrap() - 7772f64.log2()).abs() < 1e-9);
+        assert_eq!(settings.generate().split('-').count(), 200);
+    }
+
+    #[test]
+    fn without_separators_only_words_that_start_no_others_are_used() {
+        // "wort1" starts "wort10" to "wort19" and so on, which leaves only
+        // "wort0", "wort200" to "wort999" and "wort1000" to "wort1999".
+        let text: String = (0..2000).map(|i| format!("wort{}\n", i)).collect();
+        let wordlist = Wordlist::parse(&text).unwrap();
+        let settings = GeneratePassphrase::new().wordlist(wordlist.clone()).separator("");
+
+        assert_eq!(wordlist.prefix_free().len(), 1801);
+        assert!((settings.bits_per_word().unwrap() - 1801f64.log2()).abs() < 1e-9);
+        assert!((settings.entropy_bits().unwrap() - 6.0 * 1801f64.log2()).abs() < 1e-9);
+
+        let small: String = (0..1400).map(|i| format!("wort{}\n", i)).collect();
+        let settings = GeneratePassphrase::new().wordlist(Wordlist::parse(&small).unwrap()).separator("");
+        assert!(matches!(settings.validate(), Err(Error::WordlistTooSmall { .. })));
+    }
+
+    #[test]
+    fn a_passphrase_needs_words() {
+        assert!(matches!(GeneratePassphrase::new().words(0).try_generate(), Err(Error::InfeasiblePolicy(_))));
+    }
+}
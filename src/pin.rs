@@ -0,0 +1,469 @@
+//! PINs that leave out the numbers people pick most.
+
+use crate::error::Error;
+use crate::FILTER_TRIES;
+use rand::prelude::*;
+use std::collections::HashSet;
+
+/// Numeric passwords from the zxcvbn frequency list, most common first,
+/// one per line after the comments.
+pub static COMMON_PINS: &str = include_str!("wordlists/common_pins.txt");
+
+/// Shortest PIN that can be generated.
+pub const MIN_PIN_LENGTH: usize = 4;
+
+/// Longest PIN that can be generated.
+pub const MAX_PIN_LENGTH: usize = 12;
+
+/// Lines across a phone keypad, which is laid out as 123, 456, 789 and 0
+/// under the 8. A PIN that walks along one of them, over and over, in
+/// either direction and from any digit, is a keypad pattern. The last two
+/// go around the corners and around the edge.
+const KEYPAD_LINES: &[&str] = &["123", "456", "789", "147", "2580", "369", "159", "357", "1397", "12369874"];
+
+/// How dates may be written, with DD the day, MM the month, YY the last
+/// two digits of the year and YYYY a year from 1900 to 2099.
+pub(crate) const DATE_FORMATS: &[&str] = &[
+    "MMDD", "DDMM", "YYYY",
+    "DDMMYY", "MMDDYY", "YYMMDD", "MMYYYY", "YYYYMM",
+    "DDMMYYYY", "MMDDYYYY", "YYYYMMDD",
+];
+
+/// Longest block of digits that counts as repeated, as in "19901990".
+const MAX_REPEATED_BLOCK: usize = 4;
+
+/// Settings for a PIN.
+/// Default is 4 digits, leaving out every kind of weak PIN and the 100
+/// most common ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratePin {
+    length: usize,
+    repeats: bool,
+    sequences: bool,
+    keypad_patterns: bool,
+    dates: bool,
+    common: usize,
+}
+
+impl Default for GeneratePin {
+    fn default() -> Self {
+        GeneratePin {
+            length: 4,
+            repeats: true,
+            sequences: true,
+            keypad_patterns: true,
+            dates: true,
+            common: 100,
+        }
+    }
+}
+
+impl GeneratePin {
+    /// Same as `GeneratePin::default()`.
+    pub fn new() -> Self {
+        GeneratePin::default()
+    }
+
+    /// Sets the number of digits, from `MIN_PIN_LENGTH` to `MAX_PIN_LENGTH`.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Leaves out PINs that repeat a block of up to four digits, such as
+    /// "0000", "1212" or "19901990".
+    pub fn reject_repeats(mut self, reject: bool) -> Self {
+        self.repeats = reject;
+        self
+    }
+
+    /// Leaves out PINs where every digit is the last one plus the same
+    /// step, wrapping from 9 to 0, such as "1234", "7890", "4321" or "2468".
+    pub fn reject_sequences(mut self, reject: bool) -> Self {
+        self.sequences = reject;
+        self
+    }
+
+    /// Leaves out PINs that follow a line across a phone keypad, such as
+    /// "2580" or "147147", see `KEYPAD_LINES`.
+    pub fn reject_keypad_patterns(mut self, reject: bool) -> Self {
+        self.keypad_patterns = reject;
+        self
+    }
+
+    /// Leaves out PINs that read as a date or a year in one of the
+    /// `DATE_FORMATS`, such as "1225" or "19900101".
+    pub fn reject_dates(mut self, reject: bool) -> Self {
+        self.dates = reject;
+        self
+    }
+
+    /// Leaves out the `count` most common PINs of the length in
+    /// `COMMON_PINS`, or all of them if there are fewer.
+    pub fn reject_common(mut self, count: usize) -> Self {
+        self.common = count;
+        self
+    }
+
+    /// Checks that a PIN can be generated with these settings.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.length < MIN_PIN_LENGTH || self.length > MAX_PIN_LENGTH {
+            return Err(Error::LengthOutOfRange { length: self.length, min: MIN_PIN_LENGTH, max: MAX_PIN_LENGTH });
+        }
+        Ok(())
+    }
+
+    /// Whether `pin` could have been generated with these settings.
+    pub fn matches(&self, pin: &str) -> bool {
+        let common: Vec<&str> = self.common().collect();
+        pin.len() == self.length && pin.chars().all(|c| c.is_ascii_digit()) && !self.rejects(pin, &common)
+    }
+
+    /// Generates a PIN.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::GeneratePin;
+    /// let pin = GeneratePin::new().length(6).generate();
+    ///
+    /// assert_eq!(pin.len(), 6);
+    /// assert_ne!(pin, "123456");
+    /// ```
+    pub fn generate(&self) -> String {
+        match self.try_generate() {
+            Ok(pin) => pin,
+            Err(error) => panic!("Failed to generate PIN: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but draws its randomness from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        match self.try_generate_with(rng) {
+            Ok(pin) => pin,
+            Err(error) => panic!("Failed to generate PIN: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but returns an error instead of panicking
+    /// when the settings can not be met.
+    pub fn try_generate(&self) -> Result<String, Error> {
+        self.try_generate_with(&mut rand::thread_rng())
+    }
+
+    /// Same as `try_generate()`, but draws its randomness from `rng`.
+    /// Every PIN that is allowed is as likely. Random digits are tried
+    /// `FILTER_TRIES` times, which is quick and nearly always enough, as
+    /// only a small part of all PINs is left out. If none of them is
+    /// allowed, a PIN is drawn by its place among the allowed ones.
+    pub fn try_generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<String, Error> {
+        self.draw(rng, FILTER_TRIES)
+    }
+
+    /// Draws a PIN, trying random digits at most `tries` times first.
+    fn draw<R: RngCore + CryptoRng>(&self, rng: &mut R, tries: usize) -> Result<String, Error> {
+        self.validate()?;
+
+        let common: Vec<&str> = self.common().collect();
+        for _ in 0..tries {
+            let pin: String = (0..self.length)
+                .map(|_| char::from(b'0' + rng.gen_range(0, 10) as u8))
+                .collect();
+            if !self.rejects(&pin, &common) {
+                return Ok(pin);
+            }
+        }
+
+        // Listing every PIN that is left out takes long for longer PINs,
+        // so it is only done when the tries ran out.
+        let rejected: Vec<u64> = self.rejected_numbers();
+        let allowed: u64 = 10u64.pow(self.length as u32) - rejected.len() as u64;
+        if allowed == 0 {
+            return Err(Error::InfeasiblePolicy(String::from("every PIN of the length is left out")));
+        }
+        Ok(self.allowed_pin(rng.gen_range(0, allowed), &rejected))
+    }
+
+    /// The PIN at place `index` among the allowed ones, counting up from
+    /// the lowest, given the `rejected_numbers()`.
+    fn allowed_pin(&self, mut index: u64, rejected: &[u64]) -> String {
+        // Every left out number at or below the place pushes it one up.
+        for &number in rejected {
+            if number > index {
+                break;
+            }
+            index += 1;
+        }
+        format!("{:0width$}", index, width = self.length)
+    }
+
+    /// The PINs that are left out as numbers, in order.
+    fn rejected_numbers(&self) -> Vec<u64> {
+        let mut rejected: Vec<u64> = self.rejected().iter().map(|pin| pin.parse().unwrap()).collect();
+        rejected.sort_unstable();
+        rejected
+    }
+
+    /// How many bits of entropy a generated PIN has, log2 of the number of
+    /// PINs that are left after the weak ones are taken out.
+    pub fn entropy_bits(&self) -> Result<f64, Error> {
+        self.validate()?;
+
+        let all: f64 = 10f64.powi(self.length as i32);
+        Ok((all - self.rejected().len() as f64).log2())
+    }
+
+    /// Every PIN of the length that is left out.
+    fn rejected(&self) -> HashSet<String> {
+        let mut rejected: HashSet<String> = HashSet::new();
+
+        if self.repeats {
+            for block in 1..=MAX_REPEATED_BLOCK.min(self.length / 2) {
+                for number in 0..10usize.pow(block as u32) {
+                    rejected.insert(format!("{:0width$}", number, width = block).chars().cycle().take(self.length).collect());
+                }
+            }
+        }
+        if self.sequences {
+            for (first, step) in (0..10).flat_map(|first| (0..10).map(move |step| (first, step))) {
+                rejected.insert((0..self.length).map(|i| char::from(b'0' + ((first + step * i) % 10) as u8)).collect());
+            }
+        }
+        if self.keypad_patterns {
+            for line in KEYPAD_LINES {
+                let backwards: String = line.chars().rev().collect();
+                for line in [line.to_string(), backwards].iter() {
+                    for start in 0..line.len() {
+                        rejected.insert(line.chars().cycle().skip(start).take(self.length).collect());
+                    }
+                }
+            }
+        }
+        if self.dates {
+            for format in DATE_FORMATS.iter().filter(|format| format.len() == self.length) {
+                rejected.extend(dates(format));
+            }
+        }
+        rejected.extend(self.common().map(|pin| pin.to_string()));
+        rejected
+    }
+
+    /// Whether `pin` is left out, given the `common()` PINs. It must have
+    /// the right length.
+    fn rejects(&self, pin: &str, common: &[&str]) -> bool {
+        let digits: Vec<usize> = pin.bytes().map(|digit| (digit - b'0') as usize).collect();
+
+        (self.repeats && (1..=MAX_REPEATED_BLOCK.min(self.length / 2))
+            .any(|block| (block..digits.len()).all(|i| digits[i] == digits[i - block])))
+            || (self.sequences && (2..digits.len())
+                .all(|i| (digits[i] + 10 - digits[i - 1]) % 10 == (digits[1] + 10 - digits[0]) % 10))
+            || (self.keypad_patterns && is_keypad_pattern(pin))
+            || (self.dates && DATE_FORMATS.iter().any(|format| is_date(pin, format)))
+            || common.contains(&pin)
+    }
+
+    /// The most common PINs of the length that are left out.
+    fn common(&self) -> impl Iterator<Item = &'static str> + '_ {
+        COMMON_PINS.lines()
+            .filter(move |line| !line.starts_with('#') && line.len() == self.length)
+            .take(self.common)
+    }
+}
+
+/// Whether `pin` walks along one of the `KEYPAD_LINES`.
+fn is_keypad_pattern(pin: &str) -> bool {
+    let pin: &[u8] = pin.as_bytes();
+    KEYPAD_LINES.iter().any(|line| {
+        let line: &[u8] = line.as_bytes();
+        let forwards = |start: usize| (0..pin.len()).all(|i| pin[i] == line[(start + i) % line.len()]);
+        let backwards = |start: usize| (0..pin.len()).all(|i| pin[i] == line[(start + line.len() * pin.len() - i) % line.len()]);
+        (0..line.len()).any(|start| forwards(start) || backwards(start))
+    })
+}
+
+/// Whether `pin` is a date written in `format`.
+pub(crate) fn is_date(pin: &str, format: &str) -> bool {
+    if pin.len() != format.len() {
+        return false;
+    }
+    let field = |name: &str| -> Option<usize> { format.find(name).map(|at| pin[at..at + name.len()].parse().unwrap()) };
+
+    if field("YYYY").is_some_and(|year| !(1900..=2099).contains(&year)) {
+        return false;
+    }
+    let year: Option<usize> = field("YYYY").or_else(|| field("YY"));
+    match (field("DD"), field("MM")) {
+        (Some(day), Some(month)) => is_day(day, month, year),
+        (None, Some(month)) => (1..=12).contains(&month),
+        _ => true,
+    }
+}
+
+/// Every date written in `format`.
+fn dates(format: &str) -> Vec<String> {
+    let years: Vec<Option<usize>> = if format.contains("YYYY") {
+        (1900..=2099).map(Some).collect()
+    } else if format.contains("YY") {
+        (0..100).map(Some).collect()
+    } else {
+        vec![None]
+    };
+    let months: Vec<Option<usize>> = if format.contains("MM") { (1..=12).map(Some).collect() } else { vec![None] };
+    let days: Vec<Option<usize>> = if format.contains("DD") { (1..=31).map(Some).collect() } else { vec![None] };
+
+    let mut dates: Vec<String> = Vec::new();
+    for &year in years.iter() {
+        for &month in months.iter() {
+            for &day in days.iter() {
+                if let (Some(day), Some(month)) = (day, month) {
+                    if !is_day(day, month, year) {
+                        continue;
+                    }
+                }
+                let date: String = format
+                    .replace("YYYY", &year.map(|year| format!("{:04}", year)).unwrap_or_default())
+                    .replace("YY", &year.map(|year| format!("{:02}", year)).unwrap_or_default())
+                    .replace("MM", &month.map(|month| format!("{:02}", month)).unwrap_or_default())
+                    .replace("DD", &day.map(|day| format!("{:02}", day)).unwrap_or_default());
+                dates.push(date);
+            }
+        }
+    }
+    dates
+}
+
+/// Whether `day` is a day of `month`. Without a year February 29 counts.
+/// Two digit years are leap years when they are divisible by four.
+fn is_day(day: usize, month: usize, year: Option<usize>) -> bool {
+    let leap: bool = match year {
+        None => true,
+        Some(year) if year < 100 => year % 4 == 0,
+        Some(year) => year % 4 == 0 && (year % 100 != 0 || year % 400 == 0),
+    };
+    let days: usize = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+
+    /// Every PIN of `length` digits.
+    fn every_pin(length: usize) -> impl Iterator<Item = String> {
+        (0..10usize.pow(length as u32)).map(move |number| format!("{:0width$}", number, width = length))
+    }
+
+    #[test]
+    fn weak_pins_are_left_out() {
+        let settings = GeneratePin::new();
+
+        for pin in ["0000", "1212", "1234", "7890", "4321", "2468", "2580", "8520", "1397", "1225", "3112", "1990", "6969"].iter() {
+            assert!(!settings.matches(pin), "{}", pin);
+        }
+        for pin in ["4762", "8305", "5193"].iter() {
+            assert!(settings.matches(pin), "{}", pin);
+        }
+        assert!(!GeneratePin::new().length(8).matches("19901990"));
+        assert!(!GeneratePin::new().length(8).matches("25121990"));
+        assert!(!GeneratePin::new().length(6).matches("123456"));
+        assert!(!GeneratePin::new().length(6).matches("147147"));
+        assert!(GeneratePin::new().length(6).reject_common(0).reject_sequences(false).matches("123456"));
+    }
+
+    #[test]
+    fn each_rule_can_be_turned_off() {
+        let none = GeneratePin::new()
+            .reject_repeats(false)
+            .reject_sequences(false)
+            .reject_keypad_patterns(false)
+            .reject_dates(false)
+            .reject_common(0);
+
+        assert!(none.matches("0000") && none.matches("1234") && none.matches("2580") && none.matches("1225"));
+        assert!(!none.clone().reject_repeats(true).matches("0000"));
+        assert!(!none.clone().reject_sequences(true).matches("1234"));
+        assert!(!none.clone().reject_keypad_patterns(true).matches("2580"));
+        assert!(!none.clone().reject_dates(true).matches("1225"));
+        assert!(!none.reject_common(10).matches("6969"));
+    }
+
+    #[test]
+    fn left_out_pins_are_counted_exactly() {
+        for &length in [4, 5].iter() {
+            let settings = GeneratePin::new().length(length);
+            let common: Vec<&str> = settings.common().collect();
+            let allowed: usize = every_pin(length).filter(|pin| !settings.rejects(pin, &common)).count();
+
+            assert_eq!(settings.rejected().len(), 10usize.pow(length as u32) - allowed);
+            assert!((settings.entropy_bits().unwrap() - (allowed as f64).log2()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn every_allowed_pin_has_one_place() {
+        let settings = GeneratePin::new();
+        let common: Vec<&str> = settings.common().collect();
+        let allowed: Vec<String> = every_pin(4).filter(|pin| !settings.rejects(pin, &common)).collect();
+        let rejected: Vec<u64> = settings.rejected_numbers();
+
+        let placed: Vec<String> = (0..allowed.len() as u64).map(|index| settings.allowed_pin(index, &rejected)).collect();
+        assert_eq!(placed, allowed);
+
+        let mut rng = ChaCha20Rng::seed_from_u64(18);
+        for _ in 0..20 {
+            assert!(settings.matches(&settings.draw(&mut rng, 0).unwrap()));
+        }
+    }
+
+    #[test]
+    fn dates_are_read_the_same_way_they_are_written() {
+        for format in DATE_FORMATS.iter().filter(|format| format.len() <= 6) {
+            let written: HashSet<String> = dates(format).into_iter().collect();
+            let read: HashSet<String> = every_pin(format.len()).filter(|pin| is_date(pin, format)).collect();
+            assert_eq!(written, read, "{}", format);
+        }
+        assert!(is_date("29022000", "DDMMYYYY"));
+        assert!(!is_date("29021900", "DDMMYYYY"));
+        assert!(!is_date("0050", "YYYY"));
+    }
+
+    #[test]
+    fn generated_pins_are_allowed() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let settings = GeneratePin::new().length(6);
+
+        for _ in 0..200 {
+            assert!(settings.matches(&settings.generate_with(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn length_must_be_in_range() {
+        assert!(matches!(GeneratePin::new().length(3).try_generate(), Err(Error::LengthOutOfRange { .. })));
+        assert!(matches!(GeneratePin::new().length(13).entropy_bits(), Err(Error::LengthOutOfRange { .. })));
+        assert!(GeneratePin::new().length(12).entropy_bits().unwrap() < 12.0 * 10f64.log2());
+    }
+
+    #[test]
+    fn seeded_generation_gives_known_pins() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+
+        assert_eq!(GeneratePin::new().generate_with(&mut rng), "8564");
+    }
+}
@@ -0,0 +1,250 @@
+//! Password settings kept in TOML or JSON files, under profile names.
+
+use crate::error::Error;
+use crate::{CharacterSet, GeneratePassword};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The profiles that come with the crate, see `GeneratePassword::from_profile()`.
+pub static BUILTIN_PROFILES: &str = include_str!("profiles.toml");
+
+/// The settings of one profile. Fields left out of a file take the values
+/// of `GeneratePassword::default()`, a four-character number.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Password length, or the shortest length with `max_length`.
+    pub length: usize,
+    /// Longest length, for a random length from `length` up to it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub numbers: bool,
+    pub special_characters: bool,
+    pub extended_special_characters: bool,
+    /// Character sets of your own, by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub sets: BTreeMap<String, String>,
+    /// Characters that are never used.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub exclude: String,
+    pub unambiguous: bool,
+    /// Fewest characters of each named set, 1 for sets not listed.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub minimums: BTreeMap<String, usize>,
+    /// Most characters of each named set.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub maximums: BTreeMap<String, usize>,
+    /// Sets the first character may come from.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub first: Vec<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            length: 4,
+            max_length: None,
+            lowercase: false,
+            uppercase: false,
+            numbers: false,
+            special_characters: false,
+            extended_special_characters: false,
+            sets: BTreeMap::new(),
+            exclude: String::new(),
+            unambiguous: false,
+            minimums: BTreeMap::new(),
+            maximums: BTreeMap::new(),
+            first: Vec::new(),
+        }
+    }
+}
+
+impl Policy {
+    /// The generator with these settings.
+    pub fn generator(&self) -> GeneratePassword {
+        let mut generator = match self.max_length {
+            Some(max_length) => GeneratePassword::new().length_range(self.length, max_length),
+            None => GeneratePassword::new().length(self.length),
+        };
+        generator = generator
+            .lowercase(self.lowercase)
+            .uppercase(self.uppercase)
+            .numbers(self.numbers)
+            .special_characters(self.special_characters)
+            .extended_special_characters(self.extended_special_characters)
+            .exclude(&self.exclude)
+            .unambiguous(self.unambiguous);
+
+        for (name, characters) in &self.sets {
+            generator = generator.character_set(CharacterSet::new(name, characters));
+        }
+        for (set, &count) in &self.minimums {
+            generator = generator.minimum(set, count);
+        }
+        for (set, &count) in &self.maximums {
+            generator = generator.maximum(set, count);
+        }
+        if !self.first.is_empty() {
+            let first: Vec<&str> = self.first.iter().map(String::as_str).collect();
+            generator = generator.starts_with(&first);
+        }
+        generator
+    }
+}
+
+/// Named profiles, as kept in a policy file:
+///
+/// ```toml
+/// [profiles.wifi]
+/// length = 20
+/// lowercase = true
+/// numbers = true
+/// unambiguous = true
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    pub profiles: BTreeMap<String, Policy>,
+}
+
+impl PolicyFile {
+    /// The profiles that come with the crate: aws-iam, oracle and wifi.
+    pub fn builtin() -> Self {
+        PolicyFile::from_toml(BUILTIN_PROFILES).expect("the built-in profiles are valid")
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(|error| Error::PolicyUnreadable(error.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        serde_json::from_str(text).map_err(|error| Error::PolicyUnreadable(error.to_string()))
+    }
+
+    /// Reads a policy file, as JSON if its name ends in .json and as TOML
+    /// otherwise.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        let text: String = std::fs::read_to_string(path)
+            .map_err(|error| Error::PolicyUnreadable(format!("{}: {}", path.display(), error)))?;
+
+        if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json")) {
+            PolicyFile::from_json(&text)
+        } else {
+            PolicyFile::from_toml(&text)
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("policies can always be written as TOML")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("policies can always be written as JSON")
+    }
+
+    /// The profile called `name`.
+    pub fn profile(&self, name: &str) -> Result<&Policy, Error> {
+        self.profiles.get(name).ok_or_else(|| Error::UnknownProfile(name.to_string()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_profiles_generate_passwords() {
+        let profiles = PolicyFile::builtin();
+        assert_eq!(profiles.profiles.keys().collect::<Vec<_>>(), ["aws-iam", "oracle", "wifi"]);
+
+        for (name, policy) in &profiles.profiles {
+            let generator: GeneratePassword = policy.generator();
+            let password: String = generator.try_generate().unwrap_or_else(|error| panic!("{}: {}", name, error));
+            assert!(generator.matches(&password), "{}: {}", name, password);
+        }
+
+        let oracle: String = profiles.profile("oracle").unwrap().generator().generate();
+        assert!(oracle.len() <= 30);
+        assert!(oracle.starts_with(|c: char| c.is_ascii_alphabetic()));
+        assert!(oracle.chars().all(|c| c.is_ascii_alphanumeric() || "#_$".contains(c)), "{}", oracle);
+    }
+
+    #[test]
+    fn every_setting_is_read() {
+        let text: &str = r#"
+            [profiles.everything]
+            length = 12
+            max_length = 16
+            lowercase = true
+            uppercase = true
+            numbers = true
+            special_characters = true
+            extended_special_characters = true
+            exclude = "xyz"
+            unambiguous = true
+            first = ["uppercase"]
+
+            [profiles.everything.sets]
+            safe = "-_."
+
+            [profiles.everything.minimums]
+            numbers = 2
+            "special characters" = 0
+
+            [profiles.everything.maximums]
+            safe = 3
+        "#;
+        let policy: &Policy = &PolicyFile::from_toml(text).unwrap().profiles["everything"];
+
+        assert_eq!(
+            policy.generator(),
+            GeneratePassword::new()
+                .length_range(12, 16)
+                .lowercase(true)
+                .uppercase(true)
+                .numbers(true)
+                .special_characters(true)
+                .extended_special_characters(true)
+                .exclude("xyz")
+                .unambiguous(true)
+                .character_set(CharacterSet::new("safe", "-_."))
+                .minimum("numbers", 2)
+                .minimum("special characters", 0)
+                .maximum("safe", 3)
+                .starts_with(&["uppercase"])
+        );
+    }
+
+    #[test]
+    fn policies_survive_toml_and_json() {
+        let profiles = PolicyFile::builtin();
+
+        assert_eq!(PolicyFile::from_toml(&profiles.to_toml()).unwrap(), profiles);
+        assert_eq!(PolicyFile::from_json(&profiles.to_json()).unwrap(), profiles);
+        assert_eq!(PolicyFile::from_json(r#"{"profiles": {"pin": {}}}"#).unwrap().profiles["pin"], Policy::default());
+    }
+
+    #[test]
+    fn files_are_read_by_their_extension() {
+        let json = std::env::temp_dir().join(format!("policy-{}.json", std::process::id()));
+        std::fs::write(&json, PolicyFile::builtin().to_json()).unwrap();
+        let read = PolicyFile::from_file(&json);
+        std::fs::remove_file(&json).unwrap();
+
+        assert_eq!(read, Ok(PolicyFile::builtin()));
+        assert!(matches!(PolicyFile::from_file("no/such/policy.toml"), Err(Error::PolicyUnreadable(_))));
+    }
+
+    #[test]
+    fn mistakes_are_errors() {
+        assert!(matches!(PolicyFile::from_toml("[profiles.x]\nlenght = 12"), Err(Error::PolicyUnreadable(_))));
+        assert!(matches!(PolicyFile::from_toml("[profiles.x]\nlength = \"long\""), Err(Error::PolicyUnreadable(_))));
+        assert!(matches!(PolicyFile::from_json("{"), Err(Error::PolicyUnreadable(_))));
+        assert_eq!(PolicyFile::builtin().profile("mainframe"), Err(Error::UnknownProfile(String::from("mainframe"))));
+    }
+}
@@ -0,0 +1,437 @@
+//! Pronounceable passwords, modeled on the FIPS-181 syllable rules that
+//! `apg` uses.
+//!
+//! A password is made of units: consonants such as "b" or "ch" and vowels
+//! such as "e" or "ou", written one after the other with consonants and
+//! vowels taking turns. When asked for, one unit starts with an uppercase
+//! letter, and one digit and one special character go between units and
+//! start a new "word". Only one of each keeps the password easy to say.
+//!
+//! Consonant units only have consonant letters and vowel units only vowel
+//! letters, so every password can be split into units in only one way.
+//! That makes it possible to count the passwords of each length exactly
+//! and to pick every one of them with the same chance, so the entropy is
+//! log2 of that count rather than a guess.
+
+use crate::error::Error;
+use crate::{MIN_LENGTH, NUMBERS, SPECIAL_CHARACTERS};
+use rand::prelude::*;
+
+/// Longest pronounceable password. The number of passwords of this length
+/// still fits in a `f64`.
+pub const MAX_PRONOUNCEABLE_LENGTH: usize = 128;
+
+/// Consonant units, with whether they may start a word and end one.
+const CONSONANTS: &[(&str, bool, bool)] = &[
+    ("b", true, true), ("c", true, true), ("d", true, true), ("f", true, true),
+    ("g", true, true), ("h", true, false), ("j", true, false), ("k", true, true),
+    ("l", true, true), ("m", true, true), ("n", true, true), ("p", true, true),
+    ("r", true, true), ("s", true, true), ("t", true, true), ("v", true, true),
+    ("w", true, true), ("x", true, true), ("z", true, true),
+    ("ch", true, true), ("gh", false, true), ("ph", true, true), ("rh", true, false),
+    ("sh", true, true), ("th", true, true), ("wh", true, false), ("ck", false, true),
+    ("ng", false, true),
+];
+
+/// Vowel units.
+const VOWELS: &[&str] = &[
+    "a", "e", "i", "o", "u", "y",
+    "ai", "au", "ea", "ee", "ei", "ie", "oa", "oi", "oo", "ou",
+];
+
+/// Kind of the last unit, which decides what may come next.
+const START: usize = 0;
+const CONSONANT: usize = 1;
+/// A consonant that can not end a word.
+const NOT_FINAL: usize = 2;
+const VOWEL: usize = 3;
+const BREAK: usize = 4;
+const KINDS: usize = 5;
+
+/// Which guarantee a unit meets, as bits. Each is met exactly once.
+const UPPERCASE: usize = 1;
+const NUMBER: usize = 2;
+const SPECIAL: usize = 4;
+const GUARANTEES: usize = 8;
+
+/// Settings for a pronounceable password.
+/// Default is 10 lowercase letters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratePronounceable {
+    length: usize,
+    uppercase: bool,
+    numbers: bool,
+    special_characters: bool,
+}
+
+impl Default for GeneratePronounceable {
+    fn default() -> Self {
+        GeneratePronounceable {
+            length: 10,
+            uppercase: false,
+            numbers: false,
+            special_characters: false,
+        }
+    }
+}
+
+/// One unit that can be written next.
+#[derive(Clone, Debug)]
+struct Unit {
+    text: String,
+    kind: usize,
+    starts: bool,
+    meets: usize,
+}
+
+impl GeneratePronounceable {
+    /// Same as `GeneratePronounceable::default()`.
+    pub fn new() -> Self {
+        GeneratePronounceable::default()
+    }
+
+    /// Sets the length in characters.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Starts one of the units with an uppercase letter.
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Puts one digit from `NUMBERS` between the units.
+    pub fn numbers(mut self, numbers: bool) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Puts one character from `SPECIAL_CHARACTERS` between the units.
+    pub fn special_characters(mut self, special_characters: bool) -> Self {
+        self.special_characters = special_characters;
+        self
+    }
+
+    /// Checks that a password can be generated with these settings.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.table().map(|_| ())
+    }
+
+    /// Generates a pronounceable password.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::GeneratePronounceable;
+    /// let password = GeneratePronounceable::new().length(12).numbers(true).generate();
+    ///
+    /// assert_eq!(password.len(), 12);
+    /// assert!(password.chars().any(|c| c.is_ascii_digit()));
+    /// ```
+    pub fn generate(&self) -> String {
+        match self.try_generate() {
+            Ok(password) => password,
+            Err(error) => panic!("Failed to generate password: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but draws its randomness from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can not be met, see `try_generate()`.
+    pub fn generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> String {
+        match self.try_generate_with(rng) {
+            Ok(password) => password,
+            Err(error) => panic!("Failed to generate password: {}", error),
+        }
+    }
+
+    /// Same as `generate()`, but returns an error instead of panicking
+    /// when the settings can not be met.
+    pub fn try_generate(&self) -> Result<String, Error> {
+        self.try_generate_with(&mut rand::thread_rng())
+    }
+
+    /// Same as `try_generate()`, but draws its randomness from `rng`.
+    /// Every pronounceable password of the length is as likely.
+    pub fn try_generate_with<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<String, Error> {
+        let (units, table) = self.table()?;
+        Ok(self.sample(&units, &table, rng))
+    }
+
+    /// How many bits of entropy a generated password has, log2 of the
+    /// number of pronounceable passwords of the length. This is far less
+    /// than a password of random letters of the same length.
+    pub fn entropy_bits(&self) -> Result<f64, Error> {
+        let (_, table) = self.table()?;
+        Ok(table[self.length][self.state(START, 0)].log2())
+    }
+
+    /// Draws a password unit by unit, each with the chance of the
+    /// passwords that can still be made after it.
+    fn sample<R: Rng + ?Sized>(&self, units: &[Unit], table: &[Vec<f64>], rng: &mut R) -> String {
+        let mut password = String::with_capacity(self.length);
+        let mut state: usize = self.state(START, 0);
+
+        while password.len() < self.length {
+            let left: usize = self.length - password.len();
+            let odds: Vec<f64> = units.iter()
+                .map(|unit| match self.next(state, unit, left) {
+                    Some(next) => table[left - unit.text.len()][next],
+                    None => 0.0,
+                })
+                .collect();
+            let total: f64 = odds.iter().sum();
+            let mut roll: f64 = rng.gen::<f64>() * total;
+
+            // Rounding can leave a sliver at the end, so fall back to the
+            // last unit that is possible at all.
+            let mut chosen: usize = odds.iter().rposition(|&odds| odds > 0.0).unwrap_or(0);
+            for (i, &odds) in odds.iter().enumerate() {
+                if odds > 0.0 && roll < odds {
+                    chosen = i;
+                    break;
+                }
+                roll -= odds;
+            }
+
+            let unit: &Unit = &units[chosen];
+            password.push_str(&unit.text);
+            state = self.next(state, unit, left).unwrap_or(state);
+        }
+        password
+    }
+
+    /// The units that can be used with these settings.
+    fn units(&self) -> Vec<Unit> {
+        let mut units: Vec<Unit> = Vec::new();
+        let letters = CONSONANTS.iter()
+            .map(|&(text, starts, ends)| (text, if ends { CONSONANT } else { NOT_FINAL }, starts))
+            .chain(VOWELS.iter().map(|&text| (text, VOWEL, true)));
+
+        for (text, kind, starts) in letters {
+            units.push(Unit { text: text.to_string(), kind, starts, meets: 0 });
+            if self.uppercase {
+                let mut characters = text.chars();
+                let capitalized: String = characters.next().unwrap().to_uppercase().chain(characters).collect();
+                units.push(Unit { text: capitalized, kind, starts, meets: UPPERCASE });
+            }
+        }
+
+        if self.numbers {
+            units.extend(NUMBERS.chars().map(|c| Unit { text: c.to_string(), kind: BREAK, starts: true, meets: NUMBER }));
+        }
+        if self.special_characters {
+            units.extend(
+                SPECIAL_CHARACTERS.chars().map(|c| Unit { text: c.to_string(), kind: BREAK, starts: true, meets: SPECIAL })
+            );
+        }
+        units
+    }
+
+    /// The guarantees that must all be met.
+    fn required(&self) -> usize {
+        let mut required: usize = 0;
+        if self.uppercase {
+            required |= UPPERCASE;
+        }
+        if self.numbers {
+            required |= NUMBER;
+        }
+        if self.special_characters {
+            required |= SPECIAL;
+        }
+        required
+    }
+
+    /// The state for the kind of the last unit and the guarantees met.
+    fn state(&self, kind: usize, met: usize) -> usize {
+        kind * GUARANTEES + met
+    }
+
+    /// The state after writing `unit` in `state` with `left` characters to
+    /// go, or None if the unit can not come next.
+    fn next(&self, state: usize, unit: &Unit, left: usize) -> Option<usize> {
+        let (kind, met): (usize, usize) = (state / GUARANTEES, state % GUARANTEES);
+        let fits: bool = unit.text.len() <= left && met & unit.meets == 0;
+        let follows: bool = match (kind, unit.kind) {
+            (START, _) | (BREAK, CONSONANT) | (BREAK, NOT_FINAL) | (BREAK, VOWEL) => unit.starts,
+            (CONSONANT, VOWEL) | (NOT_FINAL, VOWEL) | (VOWEL, CONSONANT) | (VOWEL, NOT_FINAL) => true,
+            (VOWEL, BREAK) | (CONSONANT, BREAK) => true,
+            _ => false,
+        };
+        if fits && follows {
+            Some(self.state(unit.kind, met | unit.meets))
+        } else {
+            None
+        }
+    }
+
+    /// Checks the settings and counts the ways to finish the password from
+    /// each state: `table[left][state]` for `left` characters to go.
+    fn table(&self) -> Result<(Vec<Unit>, Vec<Vec<f64>>), Error> {
+        if self.length < MIN_LENGTH || self.length > MAX_PRONOUNCEABLE_LENGTH {
+            return Err(Error::LengthOutOfRange {
+                length: self.length,
+                min: MIN_LENGTH,
+                max: MAX_PRONOUNCEABLE_LENGTH,
+            });
+        }
+
+        let units: Vec<Unit> = self.units();
+        let required: usize = self.required();
+        let states: usize = KINDS * GUARANTEES;
+        let mut table: Vec<Vec<f64>> = vec![vec![0.0; states]; self.length + 1];
+
+        for left in 0..=self.length {
+            for state in 0..states {
+                let kind: usize = state / GUARANTEES;
+                table[left][state] = if left == 0 {
+                    let ends: bool = kind != START && kind != NOT_FINAL;
+                    if ends && state % GUARANTEES == required { 1.0 } else { 0.0 }
+                } else {
+                    units.iter()
+                        .filter_map(|unit| self.next(state, unit, left).map(|next| table[left - unit.text.len()][next]))
+                        .sum()
+                };
+            }
+        }
+
+        if table[self.length][self.state(START, 0)] == 0.0 {
+            return Err(Error::InfeasiblePolicy(format!(
+                "no pronounceable password of {} characters has every character asked for", self.length
+            )));
+        }
+        Ok((units, table))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{chi_squared, chi_squared_limit};
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::{HashMap, HashSet};
+
+    /// Every password of `length` made from `units`, by trying every way
+    /// to write them one after the other.
+    fn every_password(settings: &GeneratePronounceable, length: usize) -> Vec<String> {
+        let units: Vec<Unit> = settings.units();
+        let mut done: Vec<String> = Vec::new();
+        let mut open: Vec<(String, usize)> = vec![(String::new(), settings.state(START, 0))];
+
+        while let Some((password, state)) = open.pop() {
+            let left: usize = length - password.len();
+            let kind: usize = state / GUARANTEES;
+            if left == 0 {
+                if kind != START && kind != NOT_FINAL && state % GUARANTEES == settings.required() {
+                    done.push(password);
+                }
+                continue;
+            }
+            for unit in units.iter() {
+                if let Some(next) = settings.next(state, unit, left) {
+                    open.push((format!("{}{}", password, unit.text), next));
+                }
+            }
+        }
+        done
+    }
+
+    /// Whether consonants and vowels take turns, with digits and special
+    /// characters in between.
+    fn takes_turns(password: &str) -> bool {
+        let vowels: &str = "aeiouy";
+        let kinds: Vec<Option<bool>> = password.chars()
+            .map(|c| if c.is_alphabetic() { Some(vowels.contains(c.to_ascii_lowercase())) } else { None })
+            .collect();
+
+        // Units are at most two letters long, so three letters of the same
+        // kind in a row would be two consonants or two vowels together.
+        kinds.windows(3).all(|three| three[0].is_none() || three[0] != three[1] || three[1] != three[2])
+    }
+
+    #[test]
+    fn every_password_is_written_in_only_one_way() {
+        for settings in [
+            GeneratePronounceable::new().length(4),
+            GeneratePronounceable::new().length(4).uppercase(true).numbers(true),
+        ].iter() {
+            let passwords: Vec<String> = every_password(settings, 4);
+            let different: HashSet<&String> = passwords.iter().collect();
+
+            assert_eq!(passwords.len(), different.len());
+            assert!((settings.entropy_bits().unwrap() - (passwords.len() as f64).log2()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn every_password_is_as_likely() {
+        let settings = GeneratePronounceable::new().length(4);
+        let passwords: usize = every_password(&settings, 4).len();
+        let (units, table) = settings.table().unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let samples: usize = passwords * 10;
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for _ in 0..samples {
+            *seen.entry(settings.sample(&units, &table, &mut rng)).or_insert(0) += 1;
+        }
+
+        // The same count for every password, the ones never seen included.
+        assert!(seen.len() <= passwords);
+        let mut observed: Vec<usize> = seen.values().copied().collect();
+        observed.resize(passwords, 0);
+        let statistic: f64 = chi_squared(&observed, &vec![samples as f64 / passwords as f64; passwords]);
+        let limit: f64 = chi_squared_limit(passwords - 1);
+        assert!(statistic < limit, "chi squared {} over {}", statistic, limit);
+    }
+
+    #[test]
+    fn passwords_are_pronounceable_and_meet_the_guarantees() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let settings = GeneratePronounceable::new().length(16).uppercase(true).numbers(true).special_characters(true);
+
+        for _ in 0..50 {
+            let password: String = settings.generate_with(&mut rng);
+            assert_eq!(password.len(), 16);
+            assert!(takes_turns(&password), "{}", password);
+            assert!(password.chars().filter(|&c| NUMBERS.contains(c)).count() == 1, "{}", password);
+            assert!(password.chars().filter(|&c| SPECIAL_CHARACTERS.contains(c)).count() == 1, "{}", password);
+            assert!(password.chars().filter(|c| c.is_ascii_uppercase()).count() == 1, "{}", password);
+        }
+    }
+
+    #[test]
+    fn entropy_is_less_than_for_random_letters() {
+        let bits: f64 = GeneratePronounceable::new().length(12).entropy_bits().unwrap();
+
+        assert!(bits < 12.0 * 26f64.log2());
+        assert!(bits > 12.0 * 2.0);
+        assert!(GeneratePronounceable::new().length(12).uppercase(true).entropy_bits().unwrap() > bits);
+    }
+
+    #[test]
+    fn length_must_be_in_range() {
+        assert!(matches!(GeneratePronounceable::new().length(3).validate(), Err(Error::LengthOutOfRange { .. })));
+        assert!(matches!(
+            GeneratePronounceable::new().length(MAX_PRONOUNCEABLE_LENGTH + 1).validate(),
+            Err(Error::LengthOutOfRange { .. })
+        ));
+        assert!(GeneratePronounceable::new().length(MAX_PRONOUNCEABLE_LENGTH).special_characters(true).validate().is_ok());
+    }
+
+    #[test]
+    fn seeded_generation_gives_known_passwords() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+
+        assert_eq!(GeneratePronounceable::new().generate_with(&mut rng), "wicefiwoci");
+    }
+}
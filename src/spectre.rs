@@ -0,0 +1,268 @@
+//! Site passwords the way Spectre, formerly Master Password, derives them.
+//!
+//! The user's full name and master password are stretched with scrypt
+//! into a master key, which is slow on purpose and done once. Each site's
+//! key is HMAC-SHA256 of the site name and counter under the master key,
+//! and its bytes pick a template and then each character of it. This is
+//! version 3 of the algorithm, the one Spectre uses today.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+
+/// scrypt cost, 2^15, and its block size and parallelism.
+const LOG_N: u8 = 15;
+const R: u32 = 8;
+const P: u32 = 2;
+
+/// Scope of the master key and of site keys for passwords.
+const SCOPE: &str = "com.lyndir.masterpassword";
+
+/// What each template character stands for.
+const CLASSES: &[(char, &str)] = &[
+    ('V', "AEIOU"),
+    ('C', "BCDFGHJKLMNPQRSTVWXYZ"),
+    ('v', "aeiou"),
+    ('c', "bcdfghjklmnpqrstvwxyz"),
+    ('A', "AEIOUBCDFGHJKLMNPQRSTVWXYZ"),
+    ('a', "AEIOUaeiouBCDFGHJKLMNPQRSTVWXYZbcdfghjklmnpqrstvwxyz"),
+    ('n', "0123456789"),
+    ('o', "@&%?,=[]_:-+*$#!'^~;()/."),
+    ('x', "AEIOUaeiouBCDFGHJKLMNPQRSTVWXYZbcdfghjklmnpqrstvwxyz0123456789!@#$%^&*()"),
+    (' ', " "),
+];
+
+/// The shape of a site password, one of several patterns picked by the
+/// site key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectreTemplate {
+    /// 20 characters of anything, such as "W6@692^B1#&@gVdSdLZ@".
+    Maximum,
+    /// 14 characters you can almost say, such as "Jejr5[RepuSosp".
+    Long,
+    /// 8 characters, such as "Jej2$Quv".
+    Medium,
+    /// 4 characters, such as "Jej2".
+    Short,
+    /// 8 letters and digits, such as "WAo2xIg6".
+    Basic,
+    /// 4 digits.
+    Pin,
+    /// A 9-letter name, for logins.
+    Name,
+    /// Four lowercase words, for answers to security questions.
+    Phrase,
+}
+
+impl SpectreTemplate {
+    fn patterns(self) -> &'static [&'static str] {
+        match self {
+            SpectreTemplate::Maximum => &["anoxxxxxxxxxxxxxxxxx", "axxxxxxxxxxxxxxxxxno"],
+            SpectreTemplate::Long => &[
+                "CvcvnoCvcvCvcv", "CvcvCvcvnoCvcv", "CvcvCvcvCvcvno", "CvccnoCvcvCvcv", "CvccCvcvnoCvcv",
+                "CvccCvcvCvcvno", "CvcvnoCvccCvcv", "CvcvCvccnoCvcv", "CvcvCvccCvcvno", "CvcvnoCvcvCvcc",
+                "CvcvCvcvnoCvcc", "CvcvCvcvCvccno", "CvccnoCvccCvcv", "CvccCvccnoCvcv", "CvccCvccCvcvno",
+                "CvcvnoCvccCvcc", "CvcvCvccnoCvcc", "CvcvCvccCvccno", "CvccnoCvcvCvcc", "CvccCvcvnoCvcc",
+                "CvccCvcvCvccno",
+            ],
+            SpectreTemplate::Medium => &["CvcnoCvc", "CvcCvcno"],
+            SpectreTemplate::Short => &["Cvcn"],
+            SpectreTemplate::Basic => &["aaanaaan", "aannaaan", "aaannaaa"],
+            SpectreTemplate::Pin => &["nnnn"],
+            SpectreTemplate::Name => &["cvccvcvcv"],
+            SpectreTemplate::Phrase => &["cvcc cvc cvccvcv cvc", "cvc cvccvcvcv cvcv", "cv cvccv cvc cvcvccv"],
+        }
+    }
+}
+
+/// What the result is used for. Each has its own site keys, so a login
+/// tells nothing about the password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectrePurpose {
+    /// A password.
+    Authentication,
+    /// A login name.
+    Identification,
+    /// An answer to a security question.
+    Recovery,
+}
+
+impl SpectrePurpose {
+    /// The template Spectre uses for this purpose: Long for passwords,
+    /// Name for logins and Phrase for answers.
+    pub fn default_template(self) -> SpectreTemplate {
+        match self {
+            SpectrePurpose::Authentication => SpectreTemplate::Long,
+            SpectrePurpose::Identification => SpectreTemplate::Name,
+            SpectrePurpose::Recovery => SpectreTemplate::Phrase,
+        }
+    }
+
+    fn scope(self) -> String {
+        match self {
+            SpectrePurpose::Authentication => SCOPE.to_string(),
+            SpectrePurpose::Identification => format!("{}.login", SCOPE),
+            SpectrePurpose::Recovery => format!("{}.answer", SCOPE),
+        }
+    }
+}
+
+/// The master key of a user, from their full name and master password.
+/// Making one takes a fraction of a second and 32 MiB on purpose, so
+/// keep it for all the sites.
+#[derive(Clone)]
+pub struct SpectreKey {
+    key: [u8; 64],
+}
+
+impl SpectreKey {
+    /// Stretches `master_password` with scrypt, salted with `full_name`.
+    pub fn new(full_name: &str, master_password: &str) -> Self {
+        let params = scrypt::Params::new(LOG_N, R, P, 64).expect("the scrypt settings are valid");
+        let mut salt: Vec<u8> = SCOPE.as_bytes().to_vec();
+        push_text(&mut salt, full_name);
+
+        let mut key: [u8; 64] = [0; 64];
+        scrypt::scrypt(master_password.as_bytes(), &salt, &params, &mut key)
+            .expect("64 bytes is a valid scrypt output length");
+        SpectreKey { key }
+    }
+}
+
+impl fmt::Debug for SpectreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SpectreKey { .. }")
+    }
+}
+
+/// Settings for a site's password.
+/// Default is the Long template for a password, with counter 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeriveSpectre {
+    site: String,
+    template: SpectreTemplate,
+    counter: u32,
+    purpose: SpectrePurpose,
+    context: Option<String>,
+}
+
+impl DeriveSpectre {
+    /// Settings for `site`, usually its domain name.
+    pub fn new(site: &str) -> Self {
+        DeriveSpectre {
+            site: site.to_string(),
+            template: SpectreTemplate::Long,
+            counter: 1,
+            purpose: SpectrePurpose::Authentication,
+            context: None,
+        }
+    }
+
+    /// Sets the shape of the password.
+    pub fn template(mut self, template: SpectreTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Sets the counter, raised by one to get a new password for the site.
+    pub fn counter(mut self, counter: u32) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Sets what the result is for. The template stays as it is, but
+    /// Spectre uses the Name template for logins and Phrase for answers,
+    /// see `SpectrePurpose::default_template()`.
+    pub fn purpose(mut self, purpose: SpectrePurpose) -> Self {
+        self.purpose = purpose;
+        self
+    }
+
+    /// Mixes in more text, such as a keyword of the security question an
+    /// answer is for.
+    pub fn context(mut self, context: &str) -> Self {
+        self.context = Some(context.to_string());
+        self
+    }
+
+    /// Derives the site's password from the master key.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use password_generator::{DeriveSpectre, SpectreKey};
+    /// let key = SpectreKey::new("Robert Lee Mitchell", "banana colored duckling");
+    ///
+    /// assert_eq!(DeriveSpectre::new("masterpasswordapp.com").derive(&key), "Jejr5[RepuSosp");
+    /// ```
+    pub fn derive(&self, key: &SpectreKey) -> String {
+        let mut message: Vec<u8> = self.purpose.scope().into_bytes();
+        push_text(&mut message, &self.site);
+        message.extend_from_slice(&self.counter.to_be_bytes());
+        if let Some(context) = &self.context {
+            push_text(&mut message, context);
+        }
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key.key).expect("HMAC takes keys of any length");
+        mac.update(&message);
+        let site_key = mac.finalize().into_bytes();
+
+        let patterns: &[&str] = self.template.patterns();
+        let pattern: &str = patterns[site_key[0] as usize % patterns.len()];
+        pattern.chars()
+            .zip(site_key[1..].iter())
+            .map(|(class, &byte)| {
+                let characters: &[u8] = class_characters(class).as_bytes();
+                characters[byte as usize % characters.len()] as char
+            })
+            .collect()
+    }
+}
+
+/// The characters of a template character.
+fn class_characters(class: char) -> &'static str {
+    CLASSES.iter()
+        .find(|&&(name, _)| name == class)
+        .map(|&(_, characters)| characters)
+        .expect("templates only use known classes")
+}
+
+/// Appends `text` after its length in bytes as a big-endian 32-bit
+/// number.
+fn push_text(message: &mut Vec<u8>, text: &str) {
+    message.extend_from_slice(&(text.len() as u32).to_be_bytes());
+    message.extend_from_slice(text.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_match_the_published_test_vectors() {
+        let key = SpectreKey::new("Robert Lee Mitchell", "banana colored duckling");
+        let site = || DeriveSpectre::new("masterpasswordapp.com");
+
+        assert_eq!(site().derive(&key), "Jejr5[RepuSosp");
+        assert_eq!(site().template(SpectreTemplate::Maximum).derive(&key), "W6@692^B1#&@gVdSdLZ@");
+        assert_eq!(site().template(SpectreTemplate::Medium).derive(&key), "Jej2$Quv");
+        assert_eq!(site().template(SpectreTemplate::Short).derive(&key), "Jej2");
+        assert_eq!(site().template(SpectreTemplate::Basic).derive(&key), "WAo2xIg6");
+        assert_eq!(site().template(SpectreTemplate::Pin).derive(&key), "7662");
+        assert_eq!(site().template(SpectreTemplate::Name).derive(&key), "jejraquvo");
+        assert_eq!(site().template(SpectreTemplate::Phrase).derive(&key), "jejr quv cabsibu tam");
+        assert_eq!(site().counter(u32::MAX).derive(&key), "XambHoqo6[Peni");
+        assert_eq!(
+            site().purpose(SpectrePurpose::Identification).template(SpectreTemplate::Name).derive(&key),
+            "wohzaqage"
+        );
+        assert_eq!(
+            site().purpose(SpectrePurpose::Recovery).template(SpectreTemplate::Phrase).derive(&key),
+            "xin diyjiqoja hubu"
+        );
+        assert_eq!(
+            site().purpose(SpectrePurpose::Recovery).template(SpectreTemplate::Phrase).context("question").derive(&key),
+            "xogx tem cegyiva jab"
+        );
+    }
+}
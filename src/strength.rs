@@ -0,0 +1,780 @@
+//! How easy it is to guess a password that was not generated here.
+//!
+//! Works the way zxcvbn does: the password is split into parts such as
+//! common words, keyboard walks, dates, repeats and sequences, each with an
+//! estimate of the guesses an attacker needs for it, and anything left is
+//! guessed character by character from the classes it uses. The split that
+//! needs the fewest guesses overall is the estimate for the password.
+//! Characters are guessed from every class the password uses, out of
+//! lowercase, uppercase, numbers, special characters and anything else.
+//! A password in the blocklist of `is_common_password()` takes no more
+//! guesses than the size of the blocklist, as long as its parts do not
+//! make it more likely to be one of the filter's false positives.
+
+use crate::blocklist::{is_common_password, BLOCKLIST_FALSE_POSITIVE_RATE, BLOCKLIST_PASSWORDS};
+use crate::pin::{is_date, DATE_FORMATS};
+use crate::{EXTENDED_SPECIAL_CHARACTERS, LOWERCASE, NUMBERS, SPECIAL_CHARACTERS, UPPERCASE};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Passwords from the zxcvbn frequency list, most common first, one per
+/// line after the comments. Their rank sets the guesses they take, which
+/// the larger list behind `is_common_password()` has no order for.
+pub static COMMON_PASSWORDS: &str = include_str!("wordlists/common_passwords.txt");
+
+/// English words from the zxcvbn frequency list, most common first, one
+/// per line after the comments.
+pub static ENGLISH_WORDS: &str = include_str!("wordlists/english_words.txt");
+
+/// Only this many characters are looked at closely, the rest are guessed
+/// character by character.
+const MAX_ANALYZED_LENGTH: usize = 100;
+
+/// The rows of a US keyboard, without and with shift. Each row sits half a
+/// key further right than the one above it.
+const KEYBOARD: [(&str, &str); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// The keys next to a key, as row and column steps.
+const NEIGHBOURS: [(isize, isize); 6] = [(0, -1), (0, 1), (-1, 0), (-1, 1), (1, -1), (1, 0)];
+
+/// Characters written in place of letters.
+const L33T: &[(char, char)] = &[
+    ('4', 'a'), ('@', 'a'), ('8', 'b'), ('(', 'c'), ('{', 'c'), ('[', 'c'), ('<', 'c'), ('3', 'e'),
+    ('6', 'g'), ('9', 'g'), ('1', 'i'), ('!', 'i'), ('|', 'i'), ('1', 'l'), ('|', 'l'), ('7', 'l'),
+    ('0', 'o'), ('$', 's'), ('5', 's'), ('+', 't'), ('7', 't'), ('%', 'x'), ('2', 'z'),
+];
+
+/// Average length of a year in the Gregorian calendar.
+const SECONDS_PER_YEAR: f64 = 365.2425 * 24.0 * 60.0 * 60.0;
+
+/// Fewest guesses counted for one matched character, and for more.
+const MIN_GUESSES_SINGLE: f64 = 10.0;
+const MIN_GUESSES_MULTI: f64 = 50.0;
+
+/// What a part of a password is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// A common password or English word, `rank` 1 being the most common.
+    Dictionary { rank: usize, common_password: bool, reversed: bool, l33t: bool },
+    /// Keys next to each other on a keyboard, such as "qwerty" or "zxcvf",
+    /// changing direction `turns` - 1 times.
+    KeyboardWalk { turns: usize },
+    /// A date or a year, such as "1990" or "25/12/1990".
+    Date,
+    /// The same characters `times` times in a row, such as "aaa" or "abcabc".
+    Repeat { times: usize },
+    /// Characters going up or down by the same step, such as "abc" or "9753".
+    Sequence,
+    /// A whole password that `is_common_password()` probably knows,
+    /// guessed by trying the whole blocklist.
+    Blocklisted,
+    /// Anything else, guessed by trying every character of its classes.
+    BruteForce,
+}
+
+/// A part of a password and the guesses it takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Part {
+    token: String,
+    pattern: Pattern,
+    guesses_log10: f64,
+}
+
+impl Part {
+    /// The characters of the part.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// What the part is.
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// The base 10 log of the guesses the part takes on its own.
+    pub fn guesses_log10(&self) -> f64 {
+        self.guesses_log10
+    }
+}
+
+/// How easy a password is to guess.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Strength {
+    score: u8,
+    guesses_log10: f64,
+    parts: Vec<Part>,
+    feedback: Vec<String>,
+}
+
+impl Strength {
+    /// From 0, guessed in under a thousand tries, to 4, more than ten
+    /// billion tries. 3 and up is good enough for most online accounts.
+    pub fn score(&self) -> u8 {
+        self.score
+    }
+
+    /// The base 10 log of the guesses needed.
+    pub fn guesses_log10(&self) -> f64 {
+        self.guesses_log10
+    }
+
+    /// The parts the password was split into, in order.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// What makes the password weak and how to make it stronger, empty for
+    /// a score of 3 or 4.
+    pub fn feedback(&self) -> &[String] {
+        &self.feedback
+    }
+}
+
+/// Estimates how easy `password` is to guess.
+///
+/// Example:
+///
+/// ```
+/// # use password_generator::check_strength;
+/// assert_eq!(check_strength("password1").score(), 0);
+/// assert!(check_strength("Qm7#vR2!pZ9x").score() >= 3);
+/// ```
+pub fn check_strength(password: &str) -> Strength {
+    let characters: Vec<char> = password.chars().collect();
+    let dictionary = Dictionary::new(cardinality(&characters));
+    let analyzed: usize = characters.len().min(MAX_ANALYZED_LENGTH);
+
+    let (mut guesses_log10, mut parts): (f64, Vec<Part>) = dictionary.split(&characters[..analyzed]);
+    if analyzed < characters.len() {
+        let rest: &[char] = &characters[analyzed..];
+        let part = Part {
+            token: rest.iter().collect(),
+            pattern: Pattern::BruteForce,
+            guesses_log10: rest.len() as f64 * (dictionary.cardinality as f64).log10(),
+        };
+        guesses_log10 += part.guesses_log10;
+        parts.push(part);
+    }
+
+    // The filter also finds one in 2180 other passwords. Among passwords
+    // that take more than this many guesses by their parts, those found by
+    // mistake outnumber the listed ones, so a hit says little about them.
+    let blocklist_log10: f64 = (BLOCKLIST_PASSWORDS as f64).log10();
+    let false_positives_log10: f64 = blocklist_log10 - BLOCKLIST_FALSE_POSITIVE_RATE.log10();
+    if guesses_log10 > blocklist_log10 && guesses_log10 < false_positives_log10 && is_common_password(password) {
+        guesses_log10 = blocklist_log10;
+        parts = vec![Part { token: password.to_string(), pattern: Pattern::Blocklisted, guesses_log10 }];
+    }
+
+    let score: u8 = match guesses_log10 {
+        guesses if guesses < 3.0 => 0,
+        guesses if guesses < 6.0 => 1,
+        guesses if guesses < 8.0 => 2,
+        guesses if guesses < 10.0 => 3,
+        _ => 4,
+    };
+    let feedback: Vec<String> = if score >= 3 { Vec::new() } else { feedback(password, &parts) };
+
+    Strength { score, guesses_log10, parts, feedback }
+}
+
+/// The common passwords and English words, with their ranks, and the
+/// number of characters to try for each character that is not matched.
+struct Dictionary {
+    passwords: HashMap<&'static str, usize>,
+    words: HashMap<&'static str, usize>,
+    longest: usize,
+    cardinality: usize,
+}
+
+impl Dictionary {
+    fn new(cardinality: usize) -> Self {
+        let ranks = |list: &'static str| -> HashMap<&'static str, usize> {
+            list.lines()
+                .filter(|line| !line.starts_with('#'))
+                .enumerate()
+                .map(|(rank, word)| (word, rank + 1))
+                .collect()
+        };
+        let passwords: HashMap<&'static str, usize> = ranks(COMMON_PASSWORDS);
+        let words: HashMap<&'static str, usize> = ranks(ENGLISH_WORDS);
+        let longest: usize = passwords.keys().chain(words.keys()).map(|word| word.chars().count()).max().unwrap_or(0);
+
+        Dictionary { passwords, words, longest, cardinality }
+    }
+
+    /// The lowest rank of `word` and whether it is a common password.
+    fn rank(&self, word: &str) -> Option<(usize, bool)> {
+        match (self.passwords.get(word), self.words.get(word)) {
+            (Some(&password), Some(&word)) if word < password => Some((word, false)),
+            (Some(&password), _) => Some((password, true)),
+            (None, Some(&word)) => Some((word, false)),
+            (None, None) => None,
+        }
+    }
+
+    /// Splits `characters` into the parts that need the fewest guesses,
+    /// and returns the base 10 log of the guesses with the parts.
+    fn split(&self, characters: &[char]) -> (f64, Vec<Part>) {
+        let length: usize = characters.len();
+        if length == 0 {
+            return (0.0, Vec::new());
+        }
+
+        let mut matches: Vec<(usize, usize, Pattern, f64)> = Vec::new();
+        self.dictionary_matches(characters, &mut matches);
+        keyboard_matches(characters, &mut matches);
+        sequence_matches(characters, &mut matches);
+        date_matches(characters, &mut matches);
+        self.repeat_matches(characters, &mut matches);
+        for (start, end, _, guesses_log10) in matches.iter_mut() {
+            let least: f64 = if *end - *start == 1 { MIN_GUESSES_SINGLE } else { MIN_GUESSES_MULTI };
+            *guesses_log10 = guesses_log10.max(least.log10());
+        }
+        let per_character: f64 = (self.cardinality as f64).log10();
+        for start in 0..length {
+            for end in start + 1..=length {
+                matches.push((start, end, Pattern::BruteForce, (end - start) as f64 * per_character));
+            }
+        }
+
+        // best[end][parts] is the fewest guesses, as a log, for the first
+        // `end` characters split into `parts` parts, and which match ends
+        // the split.
+        let mut best: Vec<Vec<Option<(f64, usize)>>> = vec![vec![None; length + 1]; length + 1];
+        best[0][0] = Some((0.0, usize::MAX));
+        let mut ending: Vec<Vec<usize>> = vec![Vec::new(); length + 1];
+        for (i, &(_, end, _, _)) in matches.iter().enumerate() {
+            ending[end].push(i);
+        }
+        for end in 1..=length {
+            for &i in ending[end].iter() {
+                let (start, _, _, guesses_log10) = &matches[i];
+                for parts in 1..=end {
+                    if let Some((before, _)) = best[*start][parts - 1] {
+                        let total: f64 = before + guesses_log10;
+                        if best[end][parts].map_or(true, |(best, _)| total < best) {
+                            best[end][parts] = Some((total, i));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Guessing the number and order of the parts costs l! times the
+        // product, and an attacker tries the simpler splits first.
+        let mut fewest: Option<(f64, usize)> = None;
+        for (parts, best) in best[length].iter().enumerate().skip(1) {
+            if let Some((product, _)) = *best {
+                let factorial: f64 = (1..=parts).map(|n| (n as f64).log10()).sum();
+                let total: f64 = add_log10(factorial + product, 4.0 * (parts - 1) as f64);
+                if fewest.map_or(true, |(fewest, _)| total < fewest) {
+                    fewest = Some((total, parts));
+                }
+            }
+        }
+        let (guesses_log10, mut count): (f64, usize) = fewest.unwrap();
+
+        let mut parts: Vec<Part> = Vec::new();
+        let mut end: usize = length;
+        while count > 0 {
+            let (_, i) = best[end][count].unwrap();
+            let (start, _, pattern, guesses_log10) = &matches[i];
+            parts.push(Part {
+                token: characters[*start..end].iter().collect(),
+                pattern: pattern.clone(),
+                guesses_log10: *guesses_log10,
+            });
+            end = *start;
+            count -= 1;
+        }
+        parts.reverse();
+        (guesses_log10, parts)
+    }
+
+    /// Common passwords and words, also reversed and with l33t undone.
+    fn dictionary_matches(&self, characters: &[char], matches: &mut Vec<(usize, usize, Pattern, f64)>) {
+        for start in 0..characters.len() {
+            for end in start + 3..=characters.len().min(start + self.longest) {
+                let token: &[char] = &characters[start..end];
+                let lower: String = token.iter().collect::<String>().to_lowercase();
+                let uppercase: f64 = uppercase_variations(token);
+
+                if let Some((rank, common_password)) = self.rank(&lower) {
+                    let pattern = Pattern::Dictionary { rank, common_password, reversed: false, l33t: false };
+                    matches.push((start, end, pattern, (rank as f64 * uppercase).log10()));
+                }
+                let reversed: String = lower.chars().rev().collect();
+                if reversed != lower {
+                    if let Some((rank, common_password)) = self.rank(&reversed) {
+                        let pattern = Pattern::Dictionary { rank, common_password, reversed: true, l33t: false };
+                        matches.push((start, end, pattern, (rank as f64 * uppercase * 2.0).log10()));
+                    }
+                }
+                for (word, substitutions) in unl33t(&lower) {
+                    if let Some((rank, common_password)) = self.rank(&word) {
+                        let pattern = Pattern::Dictionary { rank, common_password, reversed: false, l33t: true };
+                        let l33t: f64 = l33t_variations(&lower, &substitutions);
+                        matches.push((start, end, pattern, (rank as f64 * uppercase * l33t).log10()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blocks written more than once in a row, guessed as the block times
+    /// the number of times.
+    fn repeat_matches(&self, characters: &[char], matches: &mut Vec<(usize, usize, Pattern, f64)>) {
+        let mut start: usize = 0;
+        while start < characters.len() {
+            // The block that repeats over the most characters from here.
+            let mut longest: Option<(usize, usize)> = None;
+            for block in 1..=(characters.len() - start) / 2 {
+                let times: usize = 1 + (1..)
+                    .take_while(|time| {
+                        let at: usize = start + time * block;
+                        at + block <= characters.len() && characters[at..at + block] == characters[start..start + block]
+                    })
+                    .count();
+                let covers: usize = block * times;
+                if times >= 2 && covers >= 3 && longest.map_or(true, |(block, times)| covers > block * times) {
+                    longest = Some((block, times));
+                }
+            }
+
+            match longest {
+                Some((block, times)) => {
+                    let (block_guesses, _) = self.split(&characters[start..start + block]);
+                    let end: usize = start + block * times;
+                    matches.push((start, end, Pattern::Repeat { times }, block_guesses + (times as f64).log10()));
+                    start = end;
+                },
+                None => start += 1,
+            }
+        }
+    }
+}
+
+/// Keys next to each other on the keyboard, at least three of them.
+fn keyboard_matches(characters: &[char], matches: &mut Vec<(usize, usize, Pattern, f64)>) {
+    let keys: HashMap<char, (isize, isize, bool)> = KEYBOARD.iter()
+        .enumerate()
+        .flat_map(|(row, (plain, shifted))| {
+            let plain = plain.chars().enumerate().map(move |(column, key)| (key, (row as isize, column as isize, false)));
+            let shifted = shifted.chars().enumerate().map(move |(column, key)| (key, (row as isize, column as isize, true)));
+            plain.chain(shifted)
+        })
+        .collect();
+    let positions: Vec<(isize, isize)> = keys.values().map(|&(row, column, _)| (row, column)).collect();
+    let starts: f64 = (positions.len() / 2) as f64;
+    let degree: f64 = positions.iter()
+        .map(|&(row, column)| {
+            NEIGHBOURS.iter().filter(|&&(down, right)| positions.contains(&(row + down, column + right))).count()
+        })
+        .sum::<usize>() as f64 / positions.len() as f64;
+
+    let step = |from: char, to: char| -> Option<usize> {
+        let (&(row, column, _), &(next_row, next_column, _)) = (keys.get(&from)?, keys.get(&to)?);
+        NEIGHBOURS.iter().position(|&step| step == (next_row - row, next_column - column))
+    };
+
+    let mut start: usize = 0;
+    while start < characters.len() {
+        let mut end: usize = start + 1;
+        let mut turns: usize = 0;
+        let mut direction: Option<usize> = None;
+        while end < characters.len() {
+            match step(characters[end - 1], characters[end]) {
+                Some(next) => {
+                    if direction != Some(next) {
+                        turns += 1;
+                        direction = Some(next);
+                    }
+                    end += 1;
+                },
+                None => break,
+            }
+        }
+
+        let length: usize = end - start;
+        if length >= 3 {
+            // Every length up to this one, with up to this many turns.
+            let mut guesses: f64 = 0.0;
+            for i in 2..=length {
+                for j in 1..=turns.min(i - 1) {
+                    guesses += choose(i - 1, j - 1) * starts * degree.powi(j as i32);
+                }
+            }
+            let shifted: usize = characters[start..end].iter().filter(|key| keys[key].2).count();
+            guesses *= case_variations(shifted, length - shifted);
+            matches.push((start, end, Pattern::KeyboardWalk { turns }, guesses.log10()));
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+/// Letters or digits going up or down by the same small step.
+fn sequence_matches(characters: &[char], matches: &mut Vec<(usize, usize, Pattern, f64)>) {
+    let class = |c: char| LOWERCASE.contains(c) as u8 | (UPPERCASE.contains(c) as u8) << 1 | (NUMBERS.contains(c) as u8) << 2;
+    let delta = |i: usize| characters[i] as i64 - characters[i - 1] as i64;
+
+    let mut start: usize = 0;
+    while start + 2 < characters.len() {
+        let first: char = characters[start];
+        let step: i64 = delta(start + 1);
+        let mut end: usize = start + 1;
+        while end < characters.len()
+            && delta(end) == step
+            && (1..=5).contains(&step.abs())
+            && class(characters[end]) == class(first)
+            && class(first) != 0
+        {
+            end += 1;
+        }
+
+        if end - start >= 3 {
+            let mut base: f64 = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if step < 0 {
+                base *= 2.0;
+            }
+            matches.push((start, end, Pattern::Sequence, (base * (end - start) as f64).log10()));
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+/// Dates written with digits only, in any of the `DATE_FORMATS`, or with
+/// the same separator between day, month and year.
+fn date_matches(characters: &[char], matches: &mut Vec<(usize, usize, Pattern, f64)>) {
+    for start in 0..characters.len() {
+        for end in start + 4..=characters.len().min(start + 10) {
+            let token: String = characters[start..end].iter().collect();
+            if let Some(guesses) = date_guesses(&token) {
+                matches.push((start, end, Pattern::Date, guesses.log10()));
+            }
+        }
+    }
+}
+
+/// The guesses for `token` if it is a date.
+fn date_guesses(token: &str) -> Option<f64> {
+    let (digits, separated): (String, bool) = if token.chars().all(|c| c.is_ascii_digit()) {
+        (token.to_string(), false)
+    } else {
+        let separator: char = token.chars().find(|c| !c.is_ascii_digit())?;
+        let fields: Vec<&str> = token.split(separator).collect();
+        let short = |field: &str| (1..=2).contains(&field.len());
+        let year = |field: &str| field.len() == 2 || field.len() == 4;
+        let ordered: bool = match fields.as_slice() {
+            [first, second, third] => (short(first) && short(second) && year(third)) || (year(first) && short(second) && short(third)),
+            _ => false,
+        };
+        if !"/\\-._ ".contains(separator) || !ordered || fields.iter().any(|field| !field.chars().all(|c| c.is_ascii_digit())) {
+            return None;
+        }
+        (fields.iter().map(|field| format!("{:0>2}", field)).collect(), true)
+    };
+
+    let format: &str = DATE_FORMATS.iter()
+        .filter(|format| !separated || (format.contains("DD") && format.contains("MM")))
+        .find(|format| is_date(&digits, format))?;
+    let year: Option<usize> = match (format.find("YYYY"), format.find("YY")) {
+        (Some(at), _) => Some(digits[at..at + 4].parse().unwrap()),
+        (None, Some(at)) => Some(digits[at..at + 2].parse::<usize>().map(|year| if year > 50 { 1900 + year } else { 2000 + year }).unwrap()),
+        (None, None) => None,
+    };
+    // Dates are measured from this year, recent years being guessed first.
+    let now: usize = current_year();
+    let years: f64 = year.map_or(1.0, |year| (year.max(now) - year.min(now)).max(20) as f64);
+    let days: f64 = if format.contains("DD") { 365.0 } else { 1.0 };
+
+    Some(years * days * if separated { 4.0 } else { 1.0 })
+}
+
+/// The year it is now, off by at most a few hours around new year.
+fn current_year() -> usize {
+    let seconds: u64 = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    1970 + (seconds as f64 / SECONDS_PER_YEAR) as usize
+}
+
+/// The number of characters in the classes `characters` uses.
+fn cardinality(characters: &[char]) -> usize {
+    let mut classes: usize = 0;
+    let has = |set: &str| characters.iter().any(|&c| set.contains(c));
+    if has(LOWERCASE) {
+        classes += LOWERCASE.len();
+    }
+    if has(UPPERCASE) {
+        classes += UPPERCASE.len();
+    }
+    if has(NUMBERS) {
+        classes += NUMBERS.len();
+    }
+    if characters.iter().any(|&c| EXTENDED_SPECIAL_CHARACTERS.contains(c) && !SPECIAL_CHARACTERS.contains(c)) {
+        classes += EXTENDED_SPECIAL_CHARACTERS.len();
+    } else if has(SPECIAL_CHARACTERS) {
+        classes += SPECIAL_CHARACTERS.len();
+    }
+    let known: String = [LOWERCASE, UPPERCASE, NUMBERS, EXTENDED_SPECIAL_CHARACTERS].concat();
+    if characters.iter().any(|&c| !known.contains(c)) {
+        classes += 100;
+    }
+    classes.max(1)
+}
+
+/// The ways to write a word in upper and lowercase that are tried before
+/// `token`: the first or last letter or all of them uppercase are cheap,
+/// other mixes less so.
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper: usize = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower: usize = token.iter().filter(|c| c.is_lowercase()).count();
+    let first_only: bool = upper == 1 && token[0].is_uppercase();
+    let last_only: bool = upper == 1 && token[token.len() - 1].is_uppercase();
+
+    if upper == 0 {
+        1.0
+    } else if first_only || last_only || lower == 0 {
+        2.0
+    } else {
+        case_variations(upper, lower)
+    }
+}
+
+/// The ways to pick which of `changed` + `unchanged` characters are changed,
+/// up to the smaller of the two.
+fn case_variations(changed: usize, unchanged: usize) -> f64 {
+    if changed == 0 || unchanged == 0 {
+        return if changed == 0 { 1.0 } else { 2.0 };
+    }
+    (1..=changed.min(unchanged)).map(|i| choose(changed + unchanged, i)).sum()
+}
+
+/// The words `lower` could be with l33t undone, each with the characters
+/// that were taken to stand for letters.
+fn unl33t(lower: &str) -> Vec<(String, Vec<(char, char)>)> {
+    let mut found: Vec<char> = lower.chars().filter(|&c| L33T.iter().any(|&(l33t, _)| l33t == c)).collect();
+    found.sort();
+    found.dedup();
+    if found.is_empty() {
+        return Vec::new();
+    }
+
+    let mut choices: Vec<Vec<(char, char)>> = vec![Vec::new()];
+    for &c in found.iter() {
+        choices = choices.iter()
+            .flat_map(|chosen| {
+                L33T.iter().filter(move |&&(l33t, _)| l33t == c).map(move |&pair| {
+                    let mut chosen: Vec<(char, char)> = chosen.clone();
+                    chosen.push(pair);
+                    chosen
+                })
+            })
+            .collect();
+    }
+
+    choices.into_iter()
+        .map(|chosen| {
+            let word: String = lower.chars()
+                .map(|c| chosen.iter().find(|&&(l33t, _)| l33t == c).map_or(c, |&(_, letter)| letter))
+                .collect();
+            (word, chosen)
+        })
+        .collect()
+}
+
+/// The ways to pick which letters were written as l33t, for every letter.
+fn l33t_variations(lower: &str, substitutions: &[(char, char)]) -> f64 {
+    substitutions.iter()
+        .map(|&(l33t, letter)| {
+            let changed: usize = lower.chars().filter(|&c| c == l33t).count();
+            let unchanged: usize = lower.chars().filter(|&c| c == letter).count();
+            case_variations(changed, unchanged)
+        })
+        .product()
+}
+
+/// The number of ways to choose `k` of `n`.
+fn choose(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |ways, i| ways * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// log10(10^a + 10^b).
+fn add_log10(a: f64, b: f64) -> f64 {
+    let (large, small): (f64, f64) = if a > b { (a, b) } else { (b, a) };
+    large + (1.0 + 10f64.powf(small - large)).log10()
+}
+
+/// What makes the password weak, from its longest part that is not just
+/// characters, and what would help.
+fn feedback(password: &str, parts: &[Part]) -> Vec<String> {
+    let mut feedback: Vec<String> = Vec::new();
+    let longest: Option<&Part> = parts.iter()
+        .filter(|part| part.pattern != Pattern::BruteForce)
+        .max_by_key(|part| part.token.chars().count());
+
+    if let Some(part) = longest {
+        let warning: &str = match part.pattern {
+            Pattern::Dictionary { rank, common_password: true, .. } if rank <= 10 && parts.len() == 1 => {
+                "This is a top-10 common password."
+            },
+            Pattern::Dictionary { rank, common_password: true, .. } if rank <= 100 && parts.len() == 1 => {
+                "This is a top-100 common password."
+            },
+            Pattern::Dictionary { common_password: true, .. } => "This is similar to a commonly used password.",
+            Pattern::Dictionary { .. } if parts.len() == 1 => "A word by itself is easy to guess.",
+            Pattern::Dictionary { .. } => "Common words are easy to guess.",
+            Pattern::KeyboardWalk { turns: 1 } => "Straight rows of keys are easy to guess.",
+            Pattern::KeyboardWalk { .. } => "Short keyboard patterns are easy to guess.",
+            Pattern::Date => "Dates are often easy to guess.",
+            Pattern::Repeat { .. } if part.token.chars().all(|c| part.token.starts_with(c)) => {
+                "Repeats like \"aaa\" are easy to guess."
+            },
+            Pattern::Repeat { .. } => "Repeats like \"abcabc\" are only slightly harder to guess than \"abc\".",
+            Pattern::Sequence => "Sequences like abc or 6543 are easy to guess.",
+            Pattern::Blocklisted => "This is probably one of the 99,838 most common passwords.",
+            Pattern::BruteForce => "",
+        };
+        feedback.push(warning.to_string());
+
+        if let Pattern::Dictionary { reversed, l33t, .. } = part.pattern {
+            let characters: Vec<char> = part.token.chars().collect();
+            if characters.iter().all(|c| !c.is_lowercase()) && characters.iter().any(|c| c.is_uppercase()) {
+                feedback.push(String::from("All-uppercase is almost as easy to guess as all-lowercase."));
+            } else if characters[0].is_uppercase() {
+                feedback.push(String::from("Capitalization doesn't help very much."));
+            }
+            if reversed {
+                feedback.push(String::from("Reversed words aren't much harder to guess."));
+            }
+            if l33t {
+                feedback.push(String::from("Predictable substitutions like '@' instead of 'a' don't help very much."));
+            }
+        }
+    }
+
+    feedback.push(String::from("Add another word or two. Uncommon words are better."));
+    let classes: [(&str, &str); 4] = [
+        ("lowercase", LOWERCASE),
+        ("uppercase", UPPERCASE),
+        ("numbers", NUMBERS),
+        ("special characters", EXTENDED_SPECIAL_CHARACTERS),
+    ];
+    let missing: Vec<&str> = classes.iter()
+        .filter(|(_, set)| !password.chars().any(|c| set.contains(c)))
+        .map(|&(name, _)| name)
+        .collect();
+    if !missing.is_empty() {
+        feedback.push(format!("Mix in characters from {}.", missing.join(", ")));
+    }
+    feedback
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(password: &str) -> Vec<Pattern> {
+        check_strength(password).parts().iter().map(|part| part.pattern().clone()).collect()
+    }
+
+    #[test]
+    fn common_passwords_are_weak() {
+        for password in ["password", "123456", "qwerty123", "iloveyou", "P@ssw0rd", "drowssap"].iter() {
+            let strength: Strength = check_strength(password);
+            assert_eq!(strength.score(), 0, "{}", password);
+            assert!(!strength.feedback().is_empty());
+        }
+        assert_eq!(check_strength("password").feedback()[0], "This is a top-10 common password.");
+        assert!(check_strength("P@ssw0rd").feedback().iter().any(|line| line.contains("substitutions")));
+        assert!(check_strength("drowssap").feedback().iter().any(|line| line.contains("Reversed")));
+    }
+
+    #[test]
+    fn blocklisted_passwords_take_no_more_guesses_than_the_blocklist() {
+        // Not in the frequency list, and no other pattern covers all of it.
+        let strength: Strength = check_strength("swaminarayan");
+
+        assert!(matches!(patterns("swaminarayan")[..], [Pattern::Blocklisted]));
+        assert!((strength.guesses_log10() - (BLOCKLIST_PASSWORDS as f64).log10()).abs() < 1e-9);
+        assert_eq!(strength.feedback()[0], "This is probably one of the 99,838 most common passwords.");
+        assert_eq!(BLOCKLIST_PASSWORDS, 99_838);
+        assert!(!matches!(patterns("swaminarayaN")[..], [Pattern::Blocklisted]));
+    }
+
+    #[test]
+    fn random_passwords_the_blocklist_finds_by_mistake_stay_strong() {
+        // Twelve random characters that the Bloom filter finds, although
+        // they are not in the list.
+        assert!(is_common_password("Qd7HhHwnBQiV"));
+        let strength: Strength = check_strength("Qd7HhHwnBQiV");
+
+        assert_eq!(strength.score(), 4);
+        assert!(matches!(patterns("Qd7HhHwnBQiV")[..], [Pattern::BruteForce]));
+    }
+
+    #[test]
+    fn patterns_are_found() {
+        assert!(matches!(patterns("zxcvfr")[..], [Pattern::KeyboardWalk { turns: 2 }]));
+        assert!(matches!(patterns("25/12/1990")[..], [Pattern::Date]));
+        assert!(matches!(patterns("19901225")[..], [Pattern::Date]));
+        assert!(current_year() >= 2026);
+        assert!(matches!(patterns("aaaaaa")[..], [Pattern::Repeat { times: 6 }]));
+        assert!(matches!(patterns("xkqxkqxkq")[..], [Pattern::Repeat { times: 3 }]));
+        assert!(matches!(patterns("acegik")[..], [Pattern::Sequence]));
+        assert!(matches!(patterns("9753")[..], [Pattern::Sequence]));
+    }
+
+    #[test]
+    fn random_passwords_are_strong() {
+        let strength: Strength = check_strength("Qm7#vR2!pZ9x");
+
+        assert_eq!(strength.score(), 4);
+        assert!(strength.feedback().is_empty());
+        assert_eq!(strength.parts().len(), 1);
+        // Twelve characters from all 72 of lowercase, uppercase, numbers
+        // and the 10 special characters.
+        assert!((strength.guesses_log10() - 12.0 * 72f64.log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn words_are_cheaper_than_their_characters() {
+        let strength: Strength = check_strength("hello world");
+
+        assert!(strength.guesses_log10() < 11.0 * 126f64.log10());
+        assert!(strength.parts().iter().any(|part| part.token() == "hello"));
+        assert!(strength.parts().iter().any(|part| part.token() == "world"));
+    }
+
+    #[test]
+    fn missing_classes_are_suggested() {
+        let feedback: Vec<String> = check_strength("sunshine").feedback().to_vec();
+
+        assert_eq!(feedback.last().unwrap(), "Mix in characters from uppercase, numbers, special characters.");
+    }
+
+    #[test]
+    fn long_and_empty_passwords_are_checked() {
+        let long: String = "Qm7#vR2!pZ9x".repeat(20);
+        let strength: Strength = check_strength(&long);
+        assert_eq!(strength.score(), 4);
+        assert_eq!(strength.parts().iter().map(|part| part.token()).collect::<String>(), long);
+
+        assert_eq!(check_strength("").score(), 0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Lists of words for passphrases.
+
+use crate::error::Error;
+use crate::passphrase::EFF_LARGE_WORDLIST;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Smallest wordlist that is accepted, 6^4 words or four dice per word.
+/// Below that each word adds too little to be worth typing.
+pub const MIN_WORDLIST_LENGTH: usize = 1296;
+
+/// The words a passphrase is made of, without duplicates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wordlist {
+    words: Vec<String>,
+    duplicates: usize,
+}
+
+impl Default for Wordlist {
+    /// The EFF long wordlist.
+    fn default() -> Self {
+        Wordlist {
+            words: EFF_LARGE_WORDLIST.lines().map(|word| word.to_string()).collect(),
+            duplicates: 0,
+        }
+    }
+}
+
+impl Wordlist {
+    /// Reads a wordlist from a UTF-8 file, see `parse()`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let text: String = fs::read_to_string(&path).map_err(|error| {
+            Error::WordlistUnreadable(format!("{}: {}", path.as_ref().display(), error))
+        })?;
+        Wordlist::parse(&text)
+    }
+
+    /// Reads a wordlist with one word per line. Lines may start with dice
+    /// numbers, as in "11111 abacus" or "11111\tabacus", which are left out.
+    /// Empty lines and lines starting with '#' are skipped. Words are kept
+    /// in lowercase, and words that are listed twice, also in another case,
+    /// are only kept once. Lists with fewer than `MIN_WORDLIST_LENGTH` words
+    /// are refused.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut words: Vec<String> = Vec::new();
+
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let word: &str = match line.split_once(char::is_whitespace) {
+                Some((dice, word)) if dice.chars().all(|c| ('1'..='6').contains(&c)) => word.trim(),
+                _ => line,
+            };
+            // "Apple" and "apple" would be the same word once capitalized.
+            words.push(word.to_lowercase());
+        }
+
+        let listed: usize = words.len();
+        let mut seen: HashSet<String> = HashSet::new();
+        words.retain(|word| seen.insert(word.clone()));
+
+        let wordlist = Wordlist { duplicates: listed - words.len(), words };
+        wordlist.check_length()?;
+        Ok(wordlist)
+    }
+
+    /// The words, in the order they were listed.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// The number of different words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// True if there are no words.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// How many words were left out because they were listed before.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    /// How many bits of entropy each word adds.
+    pub fn bits_per_word(&self) -> f64 {
+        (self.len() as f64).log2()
+    }
+
+    /// The words that are the start of another word, like "sun" and
+    /// "sunset". Without separators "sunset" + "tie" and "sun" + "settie"
+    /// could not be told apart, so two passphrases could be the same.
+    pub fn prefixes(&self) -> Vec<&str> {
+        let mut sorted: Vec<&str> = self.words.iter().map(|word| word.as_str()).collect();
+        sorted.sort();
+
+        // A word that starts another one also starts the word that comes
+        // right after it in order.
+        sorted.windows(2)
+            .filter(|pair| pair[1].starts_with(pair[0]))
+            .map(|pair| pair[0])
+            .collect()
+    }
+
+    /// The list without the words that start other words, so that words
+    /// written without separators can always be told apart.
+    pub fn prefix_free(&self) -> Wordlist {
+        let prefixes: HashSet<&str> = self.prefixes().into_iter().collect();
+        Wordlist {
+            words: self.words.iter().filter(|word| !prefixes.contains(word.as_str())).cloned().collect(),
+            duplicates: self.duplicates,
+        }
+    }
+
+    /// The list without the words that contain `separator`, so that words
+    /// joined by it can always be told apart.
+    pub fn without(&self, separator: &str) -> Wordlist {
+        Wordlist {
+            words: self.words.iter().filter(|word| !word.contains(separator)).cloned().collect(),
+            duplicates: self.duplicates,
+        }
+    }
+
+    /// Refuses lists that are too small to be safe.
+    pub(crate) fn check_length(&self) -> Result<(), Error> {
+        if self.len() < MIN_WORDLIST_LENGTH {
+            return Err(Error::WordlistTooSmall { words: self.len(), min: MIN_WORDLIST_LENGTH });
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A list of `count` made up words, "w0", "w1" and so on.
+    fn numbered(count: usize) -> String {
+        (0..count).map(|i| format!("w{}\n", i)).collect()
+    }
+
+    #[test]
+    fn plain_and_dice_numbered_lists_give_the_same_words() {
+        let plain: String = numbered(1296);
+        let numbered: String = plain.lines().enumerate()
+            .map(|(i, word)| {
+                let dice: String = (0..4).map(|roll| (b'1' + (i / 6usize.pow(roll) % 6) as u8) as char).collect();
+                format!("{}\t{}\n", dice, word)
+            })
+            .collect();
+
+        assert_eq!(Wordlist::parse(&plain).unwrap(), Wordlist::parse(&numbered).unwrap());
+        assert_eq!(Wordlist::parse(&plain).unwrap().words()[1], "w1");
+    }
+
+    #[test]
+    fn duplicates_comments_and_blank_lines_are_left_out() {
+        let text: String = format!("# my list\n\n{}w5\nw7\n", numbered(1296));
+        let wordlist = Wordlist::parse(&text).unwrap();
+
+        assert_eq!(wordlist.len(), 1296);
+        assert_eq!(wordlist.duplicates(), 2);
+        assert!((wordlist.bits_per_word() - 1296f64.log2()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn words_in_another_case_are_duplicates() {
+        let text: String = format!("{}W5\nW1000\nZebra\n", numbered(1296));
+        let wordlist = Wordlist::parse(&text).unwrap();
+
+        assert_eq!(wordlist.len(), 1297);
+        assert_eq!(wordlist.duplicates(), 2);
+        assert_eq!(wordlist.words()[1296], "zebra");
+    }
+
+    #[test]
+    fn small_lists_are_refused() {
+        assert_eq!(
+            Wordlist::parse(&numbered(1000)),
+            Err(Error::WordlistTooSmall { words: 1000, min: MIN_WORDLIST_LENGTH })
+        );
+        assert!(matches!(Wordlist::from_file("no/such/wordlist.txt"), Err(Error::WordlistUnreadable(_))));
+    }
+
+    #[test]
+    fn words_that_start_other_words_are_found() {
+        // "w1" starts "w10" to "w19" and "w100" on, and so on.
+        let wordlist = Wordlist::parse(&numbered(1296)).unwrap();
+        let prefixes: Vec<&str> = wordlist.prefixes();
+
+        assert!(prefixes.contains(&"w1"));
+        assert!(prefixes.contains(&"w12"));
+        assert!(!prefixes.contains(&"w1000"));
+        assert_eq!(wordlist.prefix_free().len(), 1296 - prefixes.len());
+        assert!(wordlist.prefix_free().prefixes().is_empty());
+    }
+
+    #[test]
+    fn the_eff_list_needs_no_separators() {
+        assert_eq!(Wordlist::default().len(), 7776);
+        assert!(Wordlist::default().prefixes().is_empty());
+    }
+
+    #[test]
+    fn words_with_the_separator_in_them_can_be_left_out() {
+        let without: Wordlist = Wordlist::default().without("-");
+
+        assert_eq!(without.len(), 7772);
+        assert!(!without.words().iter().any(|word| word == "t-shirt"));
+        assert_eq!(Wordlist::default().without(" ").len(), 7776);
+    }
+
+    #[test]
+    fn words_can_be_in_any_language() {
+        let text: String = (0..1296).map(|i| format!("{}{}\n", ["straße", "mañana", "über"][i % 3], i)).collect();
+        let wordlist = Wordlist::parse(&text).unwrap();
+
+        assert_eq!(wordlist.words()[0], "straße0");
+        assert_eq!(wordlist.words()[1], "mañana1");
+    }
+}